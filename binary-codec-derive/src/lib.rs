@@ -1,8 +1,8 @@
 extern crate proc_macro;
 
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
 use syn::{
-    parse_macro_input, punctuated::Punctuated, token::Comma, Attribute, Data, DeriveInput, Fields,
+    ext::IdentExt, parse_macro_input, spanned::Spanned, punctuated::Punctuated, token::Comma, Attribute, Data, DeriveInput, Fields,
     Lit, PathArguments, Type,
 };
 
@@ -17,7 +17,24 @@ type FieldReference<'a> = (&'a syn::Ident, Option<i32>);
         dynamic,
         dynamic_len,
         variant_by,
-        no_disc_prefix
+        no_disc_prefix,
+        disc,
+        disc_type,
+        disc_bits,
+        disc_dynamic,
+        fallback,
+        little_endian,
+        big_endian,
+        f16,
+        fixed_point,
+        bit_packing,
+        bit_order,
+        pad_bits,
+        align,
+        reserved,
+        constant,
+        magic,
+        bound
     )
 )]
 pub fn generate_code_to_bytes(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -33,7 +50,24 @@ pub fn generate_code_to_bytes(input: proc_macro::TokenStream) -> proc_macro::Tok
         dynamic,
         dynamic_len,
         variant_by,
-        no_disc_prefix
+        no_disc_prefix,
+        disc,
+        disc_type,
+        disc_bits,
+        disc_dynamic,
+        fallback,
+        little_endian,
+        big_endian,
+        f16,
+        fixed_point,
+        bit_packing,
+        bit_order,
+        pad_bits,
+        align,
+        reserved,
+        constant,
+        magic,
+        bound
     )
 )]
 pub fn generate_code_from_bytes(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -61,356 +95,824 @@ fn generate_struct_serializer(
 ) -> proc_macro::TokenStream {
     let fields = &data_struct.fields;
     let struct_name = &ast.ident;
+    let idents = get_field_idents(fields);
+    let pattern = generate_fields_pattern(fields, &idents);
+    let config_override = get_config_overrides(&ast.attrs, &ast.ident).generate();
+    let magic = generate_magic(read, &ast.attrs, &ast.ident);
 
     // Iterate all fields in the struct
-    let field_serializations = fields.iter().map(|field| {
-        let field_name = field
-            .ident
-            .as_ref()
-            .expect("ToBytes does not support fields without a name");
+    let field_serializations = generate_field_serializations(read, fields, &idents);
 
-        let field_type = &field.ty;
+    let error_type = generate_error_type(read);
+    let generics = generate_generics(read, ast);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+    let type_name = struct_name.to_string();
 
-        let mut length_determining_field = None;
-        let mut toggled_by_field = None;
-        let mut variant_by_field = None;
-        let mut bits_count_type = None;
-        let mut is_dynamic = false;
-        let mut dynamic_length_depth = None;
-
-        // Search attributes for length/toggle declarations
-        for attr in field.attrs.iter() {
-            // #[length_determined_by = "other_field"] attribute
-            // or: #[length_determined_by = "other_field.2"] for using index of array/Vec
-            if attr.path().is_ident("length_determined_by") {
-                length_determining_field = Some(get_field_name_from_attribute(
-                    "length_determined_by",
-                    attr,
-                    fields,
-                    field_name,
-                ))
+    if read {
+        // read bytes code
+        quote! {
+            impl #impl_generics binary_codec::FromBytes<'_p_de> for #struct_name #ty_generics #where_clause {
+                fn from_bytes_internal(_p_reader: &mut binary_codec::BitReader<'_p_de>, _p_config: &binary_codec::SerializationConfig) -> Result<Self, #error_type> {
+                    // Errors of the container itself (like the magic or discriminant) are at its start
+                    let _p_start = (_p_reader.position(), _p_reader.bit_offset());
+                    (|| -> Result<Self, #error_type> {
+                        #config_override
+                        #magic
+                        #(#field_serializations)*
+
+                        Ok(Self #pattern)
+                    })()
+                    .map_err(|e| e.in_type(#type_name).set_offset(_p_start.0, _p_start.1))
+                }
             }
-
-            // #[toggled_by = "other_field"] attribute
-            // or: #[toggled_by = "other_field.2"] by index of array/Vec
-            if attr.path().is_ident("toggled_by") {
-                toggled_by_field = Some(get_field_name_from_attribute(
-                    "toggled_by",
-                    attr,
-                    fields,
-                    field_name,
-                ))
+        }
+        .into()
+    } else {
+        // write bytes code
+        quote! {
+            impl #impl_generics binary_codec::ToBytes for #struct_name #ty_generics #where_clause {
+                fn to_bytes_internal(&self, _p_writer: &mut binary_codec::BitWriter, _p_config: &binary_codec::SerializationConfig) -> Result<(), #error_type> {
+                    (|| -> Result<(), #error_type> {
+                        #config_override
+                        #magic
+                        let Self #pattern = self;
+                        #(#field_serializations)*
+                        Ok(())
+                    })()
+                    .map_err(|e| e.in_type(#type_name))
+                }
             }
+        }
+        .into()
+    }
+}
+
+/// Names used for the fields in generated code. Tuple fields get synthesized names f0, f1, ...
+fn get_field_idents(fields: &Fields) -> Vec<syn::Ident> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| f.ident.clone().unwrap_or_else(|| format_ident!("f{}", i)))
+        .collect()
+}
 
-            // #[variant_by = "other_field"] attribute
-            // or: #[variant_by = "other_field.2"] by index of array/Vec
-            if attr.path().is_ident("variant_by") {
-                variant_by_field = Some(get_field_name_from_attribute(
-                    "variant_by",
-                    attr,
-                    fields,
-                    field_name,
-                ))
+/// Pattern to destructure or construct the fields: `{ a, b }`, `(f0, f1)` or nothing for unit
+fn generate_fields_pattern(fields: &Fields, idents: &[syn::Ident]) -> proc_macro2::TokenStream {
+    match fields {
+        Fields::Named(_) => quote! { { #(#idents),* } },
+        Fields::Unnamed(_) => quote! { ( #(#idents),* ) },
+        Fields::Unit => quote! {},
+    }
+}
+
+fn generate_enum_serializer(
+    read: bool,
+    ast: &DeriveInput,
+    data_enum: &syn::DataEnum,
+) -> proc_macro::TokenStream {
+    let enum_name = &ast.ident;
+    let type_name = enum_name.to_string();
+    let error_type = generate_error_type(read);
+    let generics = generate_generics(read, ast);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    let config_override = get_config_overrides(&ast.attrs, enum_name).generate();
+    let magic = generate_magic(read, &ast.attrs, enum_name);
+    let mut no_disc_prefix = false;
+    let mut disc_type = format_ident!("u8");
+    let mut disc_bits: Option<u8> = None;
+    let mut disc_dynamic = false;
+
+    // Search attributes for variant_by declarations
+    for attr in ast.attrs.iter() {
+        // #[no_disc_prefix] attribute
+        if attr.path().is_ident("no_disc_prefix") {
+            no_disc_prefix = true;
+        }
+
+        // #[disc_type = "u16"] attribute, type of the discriminant (u8, u16 or u32)
+        if attr.path().is_ident("disc_type") {
+            let ty = get_string_value_from_attribute("disc_type", attr, enum_name);
+            if !matches!(ty.as_str(), "u8" | "u16" | "u32") {
+                panic!("disc_type of '{}' should be u8, u16 or u32", enum_name);
             }
+            disc_type = format_ident!("{}", ty);
+        }
+
+        // #[disc_bits = n] attribute, bit-pack the discriminant
+        if attr.path().is_ident("disc_bits") {
+            disc_bits = Some(get_int_value_from_attribute("disc_bits", attr, enum_name));
+        }
+
+        // #[disc_dynamic] attribute, write the discriminant as dyn_int
+        if attr.path().is_ident("disc_dynamic") {
+            disc_dynamic = true;
+        }
+    }
+
+    if disc_bits.is_some() && disc_dynamic {
+        panic!("disc_bits and disc_dynamic can't be combined on '{}'", enum_name);
+    }
 
-            // #[bits = n] attribute
-            if attr.path().is_ident("bits") {
-                let bits_count = get_int_value_from_attribute("bits", attr, field_name);
-                bits_count_type = Some(bits_count as u8);
+    let disc_type_bits: u8 = disc_type.to_string()[1..].parse().unwrap();
+    if let Some(bits) = disc_bits
+        && !(1..=disc_type_bits).contains(&bits)
+    {
+        panic!("disc_bits of '{}' should be between 1 and {}", enum_name, disc_type_bits);
+    }
+
+    let disc_max: u64 = match disc_bits {
+        Some(bits) => (1 << bits) - 1,
+        None if disc_type == "u16" => u16::MAX as u64,
+        None if disc_type == "u32" => u32::MAX as u64,
+        None => u8::MAX as u64,
+    };
+
+    // Code writing _p_disc, or reading it into _p_disc
+    let dynint = generate_dynint(read);
+    let (write_disc_code, read_disc_code) = if let Some(bits) = disc_bits {
+        (
+            quote! { binary_codec::serializers::write_bits_unsigned(_p_disc as u128, _p_writer, #bits, _p_config)?; },
+            quote! { let _p_disc = binary_codec::serializers::read_bits_unsigned(_p_reader, #bits, _p_config)? as #disc_type; },
+        )
+    } else if disc_dynamic {
+        (
+            quote! {
+                let _p_dyn = _p_disc as u128;
+                #dynint
+            },
+            quote! {
+                #dynint
+                let _p_disc = <#disc_type>::try_from(_p_dyn)
+                    .map_err(|_| binary_codec::DeserializationErrorKind::UnknownDiscriminant(_p_dyn as u64))?;
+            },
+        )
+    } else {
+        (
+            quote! { binary_codec::encodings::FixedInt::write(_p_disc, _p_writer, _p_config)?; },
+            quote! { let _p_disc: #disc_type = binary_codec::encodings::FixedInt::read(_p_reader, _p_config)?; },
+        )
+    };
+
+    // A #[fallback] variant catches unknown discriminants, its first field holds the discriminant
+    let mut fallback_variants = data_enum
+        .variants
+        .iter()
+        .filter(|variant| variant.attrs.iter().any(|attr| attr.path().is_ident("fallback")));
+    let fallback_variant = fallback_variants.next();
+
+    if fallback_variants.next().is_some() {
+        panic!("Only one #[fallback] variant is allowed in '{}'", enum_name);
+    }
+
+    let fallback = if let Some(variant) = fallback_variant {
+        let var_ident = &variant.ident;
+        let var_name = var_ident.to_string();
+        let fields = &variant.fields;
+        if fields.is_empty() {
+            panic!("Fallback variant '{}' should have a field for the discriminant", var_ident);
+        }
+
+        let idents = get_field_idents(fields);
+        let disc_ident = &idents[0];
+        let pattern = generate_fields_pattern(fields, &idents);
+        let field_serializations = generate_field_serializations(read, fields, &idents);
+        let field_serializations = &field_serializations[1..];
+
+        if read {
+            quote! {
+                _ => (|| -> Result<Self, binary_codec::DeserializationError> {
+                    let #disc_ident = _p_disc;
+                    #(#field_serializations)*
+                    Ok(Self::#var_ident #pattern)
+                })()
+                .map_err(|e| e.in_variant(#var_name)),
             }
+        } else {
+            let write_disc = if no_disc_prefix {
+                quote! {}
+            } else {
+                quote! {
+                    let _p_disc: #disc_type = *#disc_ident;
+                    #write_disc_code
+                }
+            };
 
-            // #[dynamic] attribute. If put on an integer, serialize as dyn_int
-            if attr.path().is_ident("dynamic") {
-                is_dynamic = true;
+            quote! {
+                Self::#var_ident #pattern => (|| -> Result<(), binary_codec::SerializationError> {
+                    #write_disc
+                    #(#field_serializations)*
+                    Ok(())
+                })()
+                .map_err(|e| e.in_variant(#var_name))?,
             }
+        }
+    } else if read {
+        quote! {
+            _ => Err(binary_codec::DeserializationErrorKind::UnknownDiscriminant(_p_disc.into()).into()),
+        }
+    } else {
+        quote! {}
+    };
 
-            // #[dynamic_len] attribute. If put on object, Vec or String: prefix with dyn_int length
-            // If you want a Vec to inherit it, use #[dynamic_len(1)] on the Vec to inherit to 1st element
-            if attr.path().is_ident("dynamic_len") {
-                // Accept #[dynamic_len] or #[dynamic_len(value)] and extract integer if present
-                let dynamic_len_value: Option<usize> = get_int_value_from_attribute_2(attr)
-                    .or_else(|| Some(1));
+    let known_variants: Vec<&syn::Variant> = data_enum
+        .variants
+        .iter()
+        .filter(|variant| !fallback_variant.is_some_and(|fallback| std::ptr::eq(*variant, fallback)))
+        .collect();
+    let discriminants = get_discriminants(&known_variants);
 
-                dynamic_length_depth = dynamic_len_value;
-            }
+    let variants = known_variants.iter().zip(discriminants).map(|(variant, disc_value)| {
+        let var_ident = &variant.ident;
+        let var_name = var_ident.to_string();
+        if disc_value > disc_max {
+            panic!("Discriminant {} of '{}' does not fit in the discriminant type", disc_value, var_ident);
         }
+        let disc_value = proc_macro2::Literal::u64_unsuffixed(disc_value);
+        let fields = &variant.fields;
 
-        // Compose code to handle field
-        let before = if read {
+        let write_disc = if no_disc_prefix {
             quote! {}
         } else {
             quote! {
-                let _p_val = &self.#field_name;
+                let _p_disc: #disc_type = #disc_value;
+                #write_disc_code
             }
         };
 
-        let after = if read {
+        let idents = get_field_idents(fields);
+        let pattern = generate_fields_pattern(fields, &idents);
+        let field_serializations = generate_field_serializations(read, fields, &idents);
+
+        if read {
             quote! {
-                let #field_name = _p_val;
+                #disc_value => (|| -> Result<Self, binary_codec::DeserializationError> {
+                    #(#field_serializations)*
+                    Ok(Self::#var_ident #pattern)
+                })()
+                .map_err(|e| e.in_variant(#var_name))
             }
         } else {
-            quote! {}
-        };
-
-        let handle_field = generate_code_for_handling_field(
-            read,
-            field_type,
-            field_name,
-            bits_count_type,
-            is_dynamic,
-            dynamic_length_depth,
-            length_determining_field,
-            toggled_by_field,
-            variant_by_field,
-            0,
-        );
-
-        quote! {
-            #before
-            #handle_field
-            #after
+            quote! {
+                Self::#var_ident #pattern => (|| -> Result<(), binary_codec::SerializationError> {
+                    #write_disc
+                    #(#field_serializations)*
+                    Ok(())
+                })()
+                .map_err(|e| e.in_variant(#var_name))?,
+            }
         }
     });
 
-    let error_type = generate_error_type(read);
-    let serializer_code = if read {
-        let vars = fields.iter().map(|f| f.ident.as_ref().unwrap());
-
-        // read bytes code
+    if read {
         quote! {
-            pub fn from_bytes_internal(_p_bytes: &[u8], _p_pos: &mut usize, _p_bits: &mut u8) -> Result<Self, #error_type> {
-                #(#field_serializations)*
-
-                Ok(Self {
-                    #(#vars),*
-                })
+            impl #impl_generics #enum_name #ty_generics #where_clause {
+                pub fn from_bytes_internal_with_disc(_p_disc: #disc_type, _p_reader: &mut binary_codec::BitReader<'_p_de>, _p_config: &binary_codec::SerializationConfig) -> Result<Self, #error_type> {
+                    #config_override
+                    let _p_res: Result<Self, #error_type> = match _p_disc {
+                        #(#variants,)*
+                        #fallback
+                    };
+                    _p_res.map_err(|e| e.in_type(#type_name))
+                }
             }
 
-            pub fn from_bytes(bytes: &[u8]) -> Result<Self, #error_type> {
-                let mut bits = 0;
-                let mut pos = 0;
-                Self::from_bytes_internal(bytes, &mut pos, &mut bits)
+            impl #impl_generics binary_codec::FromBytes<'_p_de> for #enum_name #ty_generics #where_clause {
+                fn from_bytes_internal(_p_reader: &mut binary_codec::BitReader<'_p_de>, _p_config: &binary_codec::SerializationConfig) -> Result<Self, #error_type> {
+                    // Errors of the container itself (like the magic or discriminant) are at its start
+                    let _p_start = (_p_reader.position(), _p_reader.bit_offset());
+                    (|| -> Result<Self, #error_type> {
+                        #config_override
+                        #magic
+                        #read_disc_code
+                        Self::from_bytes_internal_with_disc(_p_disc, _p_reader, _p_config)
+                    })()
+                    .map_err(|e| e.in_type(#type_name).set_offset(_p_start.0, _p_start.1))
+                }
             }
         }
+        .into()
     } else {
-        // write bytes code
         quote! {
-            pub fn to_bytes_internal(&self, _p_bytes: &mut Vec<u8>, _p_pos: &mut usize, _p_bits: &mut u8) -> Result<(), #error_type> {
-                #(#field_serializations)*
-                Ok(())
+            impl #impl_generics binary_codec::ToBytes for #enum_name #ty_generics #where_clause {
+                fn to_bytes_internal(&self, _p_writer: &mut binary_codec::BitWriter, _p_config: &binary_codec::SerializationConfig) -> Result<(), #error_type> {
+                    (|| -> Result<(), #error_type> {
+                        #config_override
+                        #magic
+                        match self {
+                            #(#variants)*
+                            #fallback
+                        }
+                        Ok(())
+                    })()
+                    .map_err(|e| e.in_type(#type_name))
+                }
             }
+        }
+        .into()
+    }
+}
+
+/**
+ * Determine the discriminant of every variant. Like Rust does, this uses the explicit discriminant (`A = 0x10`)
+ * or the #[disc = N] attribute (for variants with fields) when present, and otherwise the previous discriminant + 1
+ */
+fn get_discriminants(variants: &[&syn::Variant]) -> Vec<u64> {
+    let mut discriminants: Vec<u64> = Vec::with_capacity(variants.len());
+
+    for variant in variants {
+        let disc_attr = variant.attrs.iter().find(|attr| attr.path().is_ident("disc"));
 
-            pub fn to_bytes(&self) -> Result<Vec<u8>, #error_type> {
-                let mut bytes = Vec::new();
-                let mut bits = 0;
-                let mut pos = 0;
-                self.to_bytes_internal(&mut bytes, &mut pos, &mut bits)?;
-                Ok(bytes)
+        let disc = if let Some(attr) = disc_attr {
+            get_int_value_from_attribute("disc", attr, &variant.ident)
+        } else if let Some((_, expr)) = &variant.discriminant {
+            if let syn::Expr::Lit(syn::ExprLit { lit: Lit::Int(lit_int), .. }) = expr {
+                lit_int
+                    .base10_parse()
+                    .unwrap_or_else(|_| panic!("Discriminant of '{}' should be a positive integer", variant.ident))
+            } else {
+                panic!("Discriminant of '{}' should be an integer literal", variant.ident)
             }
+        } else if let Some(previous) = discriminants.last() {
+            previous + 1
+        } else {
+            0
+        };
+
+        if let Some(other) = discriminants.iter().position(|d| *d == disc) {
+            panic!(
+                "Duplicate discriminant {} for variants '{}' and '{}'",
+                disc, variants[other].ident, variant.ident
+            );
         }
+
+        discriminants.push(disc);
+    }
+
+    discriminants
+}
+
+/// Generate the code for a #[magic = b"..."] container attribute, bytes written before everything else
+fn generate_magic(read: bool, attrs: &[Attribute], name: &syn::Ident) -> proc_macro2::TokenStream {
+    let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("magic")) else {
+        return quote! {};
     };
 
-    quote! {
-        impl #struct_name {
-            #serializer_code
+    let magic = match &attr.meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            value: syn::Expr::Lit(syn::ExprLit { lit: Lit::ByteStr(lit), .. }),
+            ..
+        }) => lit.value(),
+        syn::Meta::NameValue(syn::MetaNameValue {
+            value: syn::Expr::Lit(syn::ExprLit { lit: Lit::Str(lit), .. }),
+            ..
+        }) => lit.value().into_bytes(),
+        _ => panic!("Expected a byte string for magic above '{}'", name),
+    };
+
+    let magic = syn::LitByteStr::new(&magic, proc_macro2::Span::call_site());
+    if read {
+        quote! { binary_codec::serializers::read_magic(_p_reader, #magic, _p_config)?; }
+    } else {
+        quote! { binary_codec::serializers::write_magic(#magic, _p_writer, _p_config)?; }
+    }
+}
+
+/// Config values overridden by attributes on a container or field, applying to everything inside it
+#[derive(Clone, Default)]
+struct ConfigOverrides {
+    byte_order: Option<syn::Ident>,
+    bit_packing: Option<syn::Ident>,
+    bit_order: Option<syn::Ident>,
+}
+
+impl ConfigOverrides {
+    /// Generate code shadowing _p_config with the overridden values
+    fn generate(&self) -> proc_macro2::TokenStream {
+        let byte_order = self.byte_order.as_ref().map(|byte_order| {
+            quote! { .with_byte_order(binary_codec::ByteOrder::#byte_order) }
+        });
+
+        let bit_packing = self.bit_packing.as_ref().map(|bit_packing| {
+            quote! { .with_bit_packing(binary_codec::BitPacking::#bit_packing) }
+        });
+
+        let bit_order = self.bit_order.as_ref().map(|bit_order| {
+            quote! { .with_bit_order(binary_codec::BitOrder::#bit_order) }
+        });
+
+        if byte_order.is_none() && bit_packing.is_none() && bit_order.is_none() {
+            return quote! {};
+        }
+
+        quote! {
+            let _p_config = &_p_config #byte_order #bit_packing #bit_order;
         }
     }
-    .into()
 }
 
-fn generate_enum_serializer(
-    read: bool,
-    ast: &DeriveInput,
-    data_enum: &syn::DataEnum,
-) -> proc_macro::TokenStream {
-    let enum_name = &ast.ident;
-    let error_type = generate_error_type(read);
+fn get_config_overrides(attrs: &[Attribute], name: &syn::Ident) -> ConfigOverrides {
+    let mut overrides = ConfigOverrides::default();
 
-    let mut no_disc_prefix = false;
+    for attr in attrs.iter() {
+        // #[little_endian] and #[big_endian] attributes, byte order of fixed width integers
+        if attr.path().is_ident("little_endian") {
+            overrides.byte_order = Some(format_ident!("LittleEndian"));
+        }
 
-    // Search attributes for variant_by declarations
-    for attr in ast.attrs.iter() {
-        // #[no_disc_prefix] attribute
-        if attr.path().is_ident("no_disc_prefix") {
-            no_disc_prefix = true;
+        if attr.path().is_ident("big_endian") {
+            overrides.byte_order = Some(format_ident!("BigEndian"));
+        }
+
+        // #[bit_packing = "contiguous"] attribute, packing of bit fields
+        if attr.path().is_ident("bit_packing") {
+            let packing = get_string_value_from_attribute("bit_packing", attr, name);
+            overrides.bit_packing = Some(match packing.as_str() {
+                "aligned" => format_ident!("Aligned"),
+                "contiguous" => format_ident!("Contiguous"),
+                _ => panic!("bit_packing should be \"aligned\" or \"contiguous\""),
+            });
+        }
+
+        // #[bit_order = "msb"] attribute, order of bits within a byte
+        if attr.path().is_ident("bit_order") {
+            let order = get_string_value_from_attribute("bit_order", attr, name);
+            overrides.bit_order = Some(match order.as_str() {
+                "lsb" => format_ident!("Lsb"),
+                "msb" => format_ident!("Msb"),
+                _ => panic!("bit_order should be \"lsb\" or \"msb\""),
+            });
         }
     }
 
-    // Assign discriminant values starting from 0
-    let variants = data_enum.variants.iter().enumerate().map(|(i, variant)| {
-        let var_ident = &variant.ident;
-        let disc_value = i as u8; // Could be changed to u16/u32 if needed
-        let fields = &variant.fields;
+    overrides
+}
 
-        let write_disc = if no_disc_prefix {
-            quote! {}
-        } else {
-            quote! {
-                let _p_disc: u8 = #disc_value;
-                binary_codec::encodings::FixedInt::write(_p_disc, _p_bytes, _p_pos, _p_bits)?;
-            }
-        };
+/// Attributes of a field, parsed from the attributes on a struct or enum variant field
+#[derive(Clone, Default)]
+struct FieldAttributes<'a> {
+    length_determining_field: Option<FieldReference<'a>>,
+    toggled_by_field: Option<FieldReference<'a>>,
+    variant_by_field: Option<FieldReference<'a>>,
+    bits_count_type: Option<u8>,
+    is_dynamic: bool,
+    dynamic_length_depth: Option<usize>,
+    float_encoding: Option<FloatEncoding>,
+    pad_bits: Option<usize>,
+    align: Option<usize>,
+    reserved: Option<(u8, u128)>,
+    constant: Option<syn::Expr>,
+    config_overrides: ConfigOverrides,
+}
 
-        match fields {
-            Fields::Unit => {
-                if read {
-                    quote! {
-                        #disc_value => {
-                            Ok(Self::#var_ident)
-                        }
-                    }
-                } else {
-                    quote! {
-                        Self::#var_ident => {
-                            #write_disc
-                        }
-                    }
-                }
+/// Reduced precision encoding of a float field, packed into the bit stream
+#[derive(Clone)]
+enum FloatEncoding {
+    /// #[f16], IEEE-754 half float
+    Half,
+    /// #[fixed_point(scale = 100, bits = 12)], optionally with `unsigned`
+    FixedPoint { scale: f64, bits: u8, signed: bool },
+}
+
+impl FieldAttributes<'_> {
+    /// Attributes inherited by the elements of a Vec, array or map
+    fn for_elements(&self, dynamic_length_depth: Option<usize>) -> Self {
+        Self {
+            bits_count_type: self.bits_count_type,
+            is_dynamic: self.is_dynamic,
+            dynamic_length_depth,
+            float_encoding: self.float_encoding.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn get_field_attributes<'a>(
+    field: &syn::Field,
+    fields: &Fields,
+    idents: &'a [syn::Ident],
+    field_name: &syn::Ident,
+) -> FieldAttributes<'a> {
+    let mut attributes = FieldAttributes {
+        config_overrides: get_config_overrides(&field.attrs, field_name),
+        ..Default::default()
+    };
+
+    // Search attributes for length/toggle declarations
+    for attr in field.attrs.iter() {
+        // #[length_determined_by = "other_field"] attribute
+        // or: #[length_determined_by = "other_field.2"] for using index of array/Vec
+        if attr.path().is_ident("length_determined_by") {
+            attributes.length_determining_field = Some(get_field_name_from_attribute(
+                "length_determined_by",
+                attr,
+                fields,
+                idents,
+                field_name,
+            ))
+        }
+
+        // #[toggled_by = "other_field"] attribute
+        // or: #[toggled_by = "other_field.2"] by index of array/Vec
+        if attr.path().is_ident("toggled_by") {
+            attributes.toggled_by_field = Some(get_field_name_from_attribute(
+                "toggled_by",
+                attr,
+                fields,
+                idents,
+                field_name,
+            ))
+        }
+
+        // #[variant_by = "other_field"] attribute
+        // or: #[variant_by = "other_field.2"] by index of array/Vec
+        if attr.path().is_ident("variant_by") {
+            attributes.variant_by_field = Some(get_field_name_from_attribute(
+                "variant_by",
+                attr,
+                fields,
+                idents,
+                field_name,
+            ))
+        }
+
+        // #[bits = n] attribute
+        if attr.path().is_ident("bits") {
+            attributes.bits_count_type = Some(get_int_value_from_attribute("bits", attr, field_name));
+        }
+
+        // #[dynamic] attribute. If put on an integer, serialize as dyn_int
+        if attr.path().is_ident("dynamic") {
+            attributes.is_dynamic = true;
+        }
+
+        // #[f16] attribute. If put on a float, serialize as half float
+        if attr.path().is_ident("f16") {
+            attributes.float_encoding = Some(FloatEncoding::Half);
+        }
+
+        // #[fixed_point(scale = 100, bits = 12)] attribute. If put on a float, serialize as scaled integer
+        if attr.path().is_ident("fixed_point") {
+            attributes.float_encoding = Some(get_fixed_point_from_attribute(attr, field_name));
+        }
+
+        // #[pad_bits = n] attribute, write n zero bits before the field
+        if attr.path().is_ident("pad_bits") {
+            attributes.pad_bits = Some(get_int_value_from_attribute("pad_bits", attr, field_name));
+        }
+
+        // #[align = n] attribute, pad with zero bytes before the field until the position is a multiple of n
+        if attr.path().is_ident("align") {
+            let align: usize = get_int_value_from_attribute("align", attr, field_name);
+            if align == 0 {
+                panic!("align of '{}' should be at least 1", field_name);
             }
-            Fields::Unnamed(fields_unnamed) => {
-                let field_count = fields_unnamed.unnamed.len();
-                let idents: Vec<_> = (0..field_count).map(|i| format_ident!("f{}", i)).collect();
-                let ident_refs: Vec<&syn::Ident> = idents.iter().collect();
-                let field_serializations =
-                    generate_enum_field_serializations(read, &ident_refs, &fields_unnamed.unnamed);
-                if read {
-                    quote! {
-                        #disc_value => {
-                            #(#field_serializations)*
-                            Ok(Self::#var_ident(#(#idents),*))
-                        }
-                    }
-                } else {
-                    quote! {
-                        Self::#var_ident(#(#idents),*) => {
-                            #write_disc
-                            #(#field_serializations)*
-                        }
-                    }
-                }
+            attributes.align = Some(align);
+        }
+
+        // #[reserved(bits = n, value = 0)] attribute on a () field, constant bits validated on read
+        if attr.path().is_ident("reserved") {
+            attributes.reserved = Some(get_reserved_from_attribute(attr, field_name));
+        }
+
+        // #[constant = 0x7E] attribute on a number field, always written and validated on read
+        if attr.path().is_ident("constant") {
+            if let syn::Meta::NameValue(name_value) = &attr.meta {
+                attributes.constant = Some(name_value.value.clone());
+            } else {
+                panic!("Expected a value for constant above '{}'", field_name);
             }
-            Fields::Named(fields_named) => {
-                let field_idents: Vec<_> = fields_named
-                    .named
-                    .iter()
-                    .map(|f| f.ident.as_ref().unwrap())
-                    .collect();
+        }
 
-                let field_serializations =
-                    generate_enum_field_serializations(read, &field_idents, &fields_named.named);
+        // #[dynamic_len] attribute. If put on object, Vec or String: prefix with dyn_int length
+        // If you want a Vec to inherit it, use #[dynamic_len(1)] on the Vec to inherit to 1st element
+        if attr.path().is_ident("dynamic_len") {
+            // Accept #[dynamic_len] or #[dynamic_len(value)] and extract integer if present
+            attributes.dynamic_length_depth = get_int_value_from_attribute_2(attr).or(Some(1));
+        }
+    }
 
-                if read {
-                    quote! {
-                        #disc_value => {
-                            #(#field_serializations)*
-                            Ok(Self::#var_ident { #(#field_idents),* })
-                        }
-                    }
-                } else {
+    attributes
+}
+
+/// Generate the code for all fields of a struct or enum variant.
+/// When writing, the fields are expected to be destructured into references named after `idents`,
+/// when reading the fields are read into variables named after `idents`.
+fn generate_field_serializations(
+    read: bool,
+    fields: &Fields,
+    idents: &[syn::Ident],
+) -> Vec<proc_macro2::TokenStream> {
+    let field_serializations = fields.iter().zip(idents.iter()).enumerate().map(|(i, (field, field_name))| {
+        let attributes = get_field_attributes(field, fields, idents, field_name);
+        // Name in the error path, the position for tuple fields
+        let label = field.ident.as_ref().map_or_else(|| i.to_string(), |ident| ident.unraw().to_string());
+        let context = if read {
+            quote! { in_field(#label, _p_start.0, _p_start.1) }
+        } else {
+            quote! { in_field(#label) }
+        };
+        let handle_field = if let Some((bits, value)) = attributes.reserved {
+            generate_reserved(read, &field.ty, field_name, bits, value)
+        } else {
+            generate_code_for_handling_field(read, &field.ty, field_name, &attributes, 0)
+        };
+        let config_override = attributes.config_overrides.generate();
+        let padding = generate_padding(read, &attributes);
+        let field_type = &field.ty;
+
+        if let Some(constant) = &attributes.constant {
+            // Checked at compile time, so a constant that doesn't fit the field type fails to compile
+            // instead of being truncated by the cast. Attribute values are literals, all positive
+            let (value, wide_type) = match constant {
+                syn::Expr::Lit(syn::ExprLit { lit: Lit::Float(float), .. }) => {
+                    let value = float.base10_parse::<f64>().expect("Invalid float constant");
+                    (quote! { #value }, quote! { f64 })
+                }
+                syn::Expr::Lit(syn::ExprLit { lit, .. }) => {
+                    let value = match lit {
+                        Lit::Int(int) => int.base10_parse::<u128>().expect("Integer constant doesn't fit u128"),
+                        Lit::Byte(byte) => byte.value() as u128,
+                        Lit::Char(char) => char.value() as u128,
+                        _ => panic!("Expected a number as constant above '{}'", field_name),
+                    };
+                    (quote! { #value }, quote! { u128 })
+                }
+                _ => panic!("Expected a number as constant above '{}'", field_name),
+            };
+            // Spanned to the attribute value, so that is where the compile error points
+            let constant = quote_spanned! {constant.span()=> {
+                const _P_CONSTANT: #field_type = {
+                    let _p_cast = #value as #field_type;
+                    assert!(_p_cast as #wide_type == #value, "the constant doesn't fit the field type");
+                    _p_cast
+                };
+                _P_CONSTANT
+            }};
+
+            // The field value is ignored, the constant is written instead
+            return if read {
+                let read_field = generate_in_context(
+                    true,
+                    field_type,
                     quote! {
-                        Self::#var_ident { #(#field_idents),* } => {
-                            #write_disc
-                            #(#field_serializations)*
+                        #config_override
+                        #padding
+                        #handle_field
+                        let _p_val: #field_type = _p_val;
+                        let _p_expected = #constant;
+                        if _p_val != _p_expected {
+                            return Err(binary_codec::DeserializationErrorKind::InvalidMagic {
+                                expected: _p_expected.to_be_bytes().to_vec(),
+                                found: _p_val.to_be_bytes().to_vec(),
+                            }.into());
                         }
-                    }
+                    },
+                    context,
+                );
+                quote! {
+                    let #field_name = {
+                        #read_field
+                        _p_val
+                    };
                 }
+            } else {
+                generate_in_context(
+                    false,
+                    field_type,
+                    quote! {
+                        let _ = #field_name;
+                        let _p_val = &#constant;
+                        #config_override
+                        #padding
+                        #handle_field
+                    },
+                    context,
+                )
+            };
+        }
+
+        if read {
+            let read_field = generate_in_context(
+                true,
+                field_type,
+                quote! {
+                    #config_override
+                    #padding
+                    #handle_field
+                },
+                context,
+            );
+            quote! {
+                let #field_name = {
+                    #read_field
+                    _p_val
+                };
             }
+        } else {
+            generate_in_context(
+                false,
+                field_type,
+                quote! {
+                    let _p_val = #field_name;
+                    #config_override
+                    #padding
+                    #handle_field
+                },
+                context,
+            )
         }
     });
+    field_serializations.collect()
+}
 
+/**
+ * Run the code of a value in a closure, so errors can get context (like the field or element) before they
+ * are returned. `context` is called on the error. When reading, the code should leave the value in _p_val,
+ * and _p_start holds the position where the value starts.
+ */
+fn generate_in_context(
+    read: bool,
+    value_type: &Type,
+    code: proc_macro2::TokenStream,
+    context: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
     if read {
         quote! {
-            impl #enum_name {
-                pub fn from_bytes_internal_with_disc(_p_disc: u8, _p_bytes: &[u8], _p_pos: &mut usize, _p_bits: &mut u8) -> Result<Self, #error_type> {
-                    match _p_disc {
-                        #(#variants,)*
-                        _ => Err(#error_type::UnknownDiscriminant(_p_disc)),
-                    }
-                }
-
-                pub fn from_bytes_internal(bytes: &[u8], pos: &mut usize, bits: &mut u8) -> Result<Self, #error_type> {
-                    let _p_disc: u8 = binary_codec::encodings::FixedInt::read(bytes, pos, bits)?;
-                    Self::from_bytes_internal_with_disc(_p_disc, bytes, pos, bits)
-                }
-
-                pub fn from_bytes(bytes: &[u8]) -> Result<Self, #error_type> {
-                    let mut pos = 0;
-                    let mut bits = 0;
-                    Self::from_bytes_internal(bytes, &mut pos, &mut bits)
-                }
-            }
+            let _p_start = (_p_reader.position(), _p_reader.bit_offset());
+            let _p_val = (|| -> Result<#value_type, binary_codec::DeserializationError> {
+                #code
+                Ok(_p_val)
+            })()
+            .map_err(|e| e.#context)?;
         }
-        .into()
     } else {
         quote! {
-            impl #enum_name {
-                pub fn to_bytes_internal(&self, _p_bytes: &mut Vec<u8>, _p_pos: &mut usize, _p_bits: &mut u8) -> Result<(), #error_type> {
-                    match self {
-                        #(#variants)*
-                    }
-                    Ok(())
-                }
+            (|| -> Result<(), binary_codec::SerializationError> {
+                #code
+                Ok(())
+            })()
+            .map_err(|e| e.#context)?;
+        }
+    }
+}
 
-                pub fn to_bytes(&self) -> Result<Vec<u8>, #error_type> {
-                    let mut bytes = Vec::new();
-                    let mut pos = 0;
-                    let mut bits = 0;
-                    self.to_bytes_internal(&mut bytes, &mut pos, &mut bits)?;
-                    Ok(bytes)
-                }
-            }
+/// Generate the code for #[align] and #[pad_bits], written before the field
+fn generate_padding(read: bool, attributes: &FieldAttributes) -> proc_macro2::TokenStream {
+    let align = attributes.align.map(|align| {
+        if read {
+            quote! { binary_codec::serializers::read_alignment(_p_reader, #align, _p_config)?; }
+        } else {
+            quote! { binary_codec::serializers::write_alignment(_p_writer, #align, _p_config)?; }
+        }
+    });
+
+    let pad_bits = attributes.pad_bits.map(|pad_bits| {
+        if read {
+            quote! { binary_codec::serializers::read_padding(_p_reader, #pad_bits, _p_config)?; }
+        } else {
+            quote! { binary_codec::serializers::write_padding(_p_writer, #pad_bits, _p_config)?; }
         }
-        .into()
+    });
+
+    quote! {
+        #align
+        #pad_bits
     }
 }
 
-fn generate_enum_field_serializations(
+/// Generate the code for a #[reserved] marker field, which should be of type ()
+fn generate_reserved(
     read: bool,
-    idents: &Vec<&syn::Ident>,
-    fields: &Punctuated<syn::Field, Comma>,
-) -> Vec<proc_macro2::TokenStream> {
-    let field_serializations = fields.iter().enumerate().map(|(i, f)| {
-        let field_type = &f.ty;
-        let field_ident = &idents[i];
-
-        let handle_field = generate_code_for_handling_field(
-            read,
-            field_type,
-            field_ident,
-            None,
-            false,
-            None,
-            None,
-            None,
-            None,
-            0,
-        );
+    field_type: &Type,
+    field_name: &syn::Ident,
+    bits: u8,
+    value: u128,
+) -> proc_macro2::TokenStream {
+    if !matches!(field_type, Type::Tuple(tuple) if tuple.elems.is_empty()) {
+        panic!("Reserved field '{}' should be of type ()", field_name);
+    }
 
-        if read {
-            quote! {
-                #handle_field
-                let #field_ident = _p_val;
-            }
-        } else {
-            quote! {
-                let _p_val = #field_ident;
-                #handle_field
-            }
+    if read {
+        quote! {
+            binary_codec::serializers::read_reserved(_p_reader, #bits, #value, _p_config)?;
+            let _p_val = ();
         }
-    });
-    field_serializations.collect()
+    } else {
+        quote! {
+            let _ = _p_val;
+            binary_codec::serializers::write_reserved(#value, _p_writer, #bits, _p_config)?;
+        }
+    }
 }
 
 fn generate_code_for_handling_field(
     read: bool,
     field_type: &Type,
     field_name: &syn::Ident,
-    bits_count_type: Option<u8>,
-    is_dynamic: bool,
-    dynamic_length_depth: Option<usize>,
-    length_determining_field: Option<FieldReference>,
-    toggled_by_field: Option<FieldReference>,
-    variant_by_field: Option<FieldReference>,
+    attributes: &FieldAttributes,
     level: usize,
 ) -> proc_macro2::TokenStream {
     if let Type::Path(path) = field_type {
@@ -427,59 +929,60 @@ fn generate_code_for_handling_field(
             match ident_name.as_str() {
                 "bool" => {
                     if read {
-                        quote! { let _p_val = binary_codec::serializers::read_bool(_p_bytes, _p_pos, _p_bits)?; }
+                        quote! { let _p_val = binary_codec::serializers::read_bool(_p_reader, _p_config)?; }
                     } else {
-                        quote! { binary_codec::serializers::write_bool(*_p_val, _p_bytes, _p_pos, _p_bits)?; }
+                        quote! { binary_codec::serializers::write_bool(*_p_val, _p_writer, _p_config)?; }
                     }
                 }
-                "i8" => {
-                    if let Some(bits_count) = bits_count_type.as_ref() {
-                        if *bits_count < 1 || *bits_count > 7 {
-                            panic!("Bits count should be between 1 and 7");
-                        }
+                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128"
+                    if attributes.bits_count_type.is_some() =>
+                {
+                    let bits_count = attributes.bits_count_type.unwrap();
+                    let type_bits: u8 = ident_name[1..].parse().unwrap();
+                    if bits_count < 1 || bits_count > type_bits {
+                        panic!("Bits count of '{}' should be between 1 and {}", field_name, type_bits);
+                    }
 
-                        if read {
-                            quote! { let _p_val = binary_codec::serializers::read_small_dynamic_signed(_p_bytes, _p_pos, _p_bits, #bits_count)?; }
-                        } else {
-                            quote! { binary_codec::serializers::write_small_dynamic_signed(*_p_val, _p_bytes, _p_pos, _p_bits, #bits_count)?; }
+                    let signed = ident_name.starts_with('i');
+                    match (read, signed) {
+                        (true, false) => quote! {
+                            let _p_val = binary_codec::serializers::read_bits_unsigned(_p_reader, #bits_count, _p_config)? as #ident;
+                        },
+                        (true, true) => quote! {
+                            let _p_val = binary_codec::serializers::read_bits_signed(_p_reader, #bits_count, _p_config)? as #ident;
+                        },
+                        (false, false) => quote! {
+                            binary_codec::serializers::write_bits_unsigned(*_p_val as u128, _p_writer, #bits_count, _p_config)?;
+                        },
+                        (false, true) => quote! {
+                            binary_codec::serializers::write_bits_signed(*_p_val as i128, _p_writer, #bits_count, _p_config)?;
+                        },
+                    }
+                }
+                "i8" => {
+                    if read {
+                        quote! {
+                            let _p_val = binary_codec::encodings::read_zigzag(_p_reader, _p_config)?;
                         }
                     } else {
-                        if read {
-                            quote! {
-                                let _p_val = binary_codec::encodings::read_zigzag(_p_bytes, _p_pos, _p_bits)?;
-                            }
-                        } else {
-                            quote! {
-                                binary_codec::encodings::write_zigzag(*_p_val, _p_bytes, _p_pos, _p_bits)?;
-                            }
+                        quote! {
+                            binary_codec::encodings::write_zigzag(*_p_val, _p_writer, _p_config)?;
                         }
                     }
                 }
                 "u8" => {
-                    if let Some(bits_count) = bits_count_type.as_ref() {
-                        if *bits_count < 1 || *bits_count > 7 {
-                            panic!("Bits count should be between 1 and 7");
-                        }
-
-                        if read {
-                            quote! { let _p_val = binary_codec::serializers::read_small_dynamic_unsigned(_p_bytes, _p_pos, _p_bits, #bits_count)?; }
-                        } else {
-                            quote! { binary_codec::serializers::write_small_dynamic_unsigned(*_p_val, _p_bytes, _p_pos, _p_bits, #bits_count)?; }
+                    if read {
+                        quote! {
+                            let _p_val = binary_codec::encodings::FixedInt::read(_p_reader, _p_config)?;
                         }
                     } else {
-                        if read {
-                            quote! {
-                                let _p_val = binary_codec::encodings::FixedInt::read(_p_bytes, _p_pos, _p_bits)?;
-                            }
-                        } else {
-                            quote! {
-                                binary_codec::encodings::FixedInt::write(*_p_val, _p_bytes, _p_pos, _p_bits)?;
-                            }
+                        quote! {
+                            binary_codec::encodings::FixedInt::write(*_p_val, _p_writer, _p_config)?;
                         }
                     }
                 }
                 "u16" | "u32" | "u64" | "u128" => {
-                    if is_dynamic {
+                    if attributes.is_dynamic {
                         let dynint: proc_macro2::TokenStream = generate_dynint(read);
                         if read {
                             quote! {
@@ -495,17 +998,17 @@ fn generate_code_for_handling_field(
                     } else {
                         if read {
                             quote! {
-                                let _p_val = binary_codec::encodings::FixedInt::read(_p_bytes, _p_pos, _p_bits)?;
+                                let _p_val = binary_codec::encodings::FixedInt::read(_p_reader, _p_config)?;
                             }
                         } else {
                             quote! {
-                                binary_codec::encodings::FixedInt::write(*_p_val, _p_bytes, _p_pos, _p_bits)?;
+                                binary_codec::encodings::FixedInt::write(*_p_val, _p_writer, _p_config)?;
                             }
                         }
                     }
                 }
                 "i16" | "i32" | "i64" | "i128" => {
-                    if is_dynamic {
+                    if attributes.is_dynamic {
                         let dynint: proc_macro2::TokenStream = generate_dynint(read);
                         if read {
                             quote! {
@@ -521,124 +1024,52 @@ fn generate_code_for_handling_field(
                     } else {
                         if read {
                             quote! {
-                                let _p_val = binary_codec::encodings::read_zigzag(_p_bytes, _p_pos, _p_bits)?;
+                                let _p_val = binary_codec::encodings::read_zigzag(_p_reader, _p_config)?;
                             }
                         } else {
                             quote! {
-                                binary_codec::encodings::write_zigzag(*_p_val, _p_bytes, _p_pos, _p_bits)?;
+                                binary_codec::encodings::write_zigzag(*_p_val, _p_writer, _p_config)?;
                             }
                         }
                     }
                 }
-                "String" => {
-                    // Read and write for String based on two strategies:
-                    // 1. using length_determining_field like we do for options's toggled_by. Cast the field to usize
-                    // 2. using space left if has_dynamic_len is not set
-                    // 3. Try to read space from dyn_int.
-
-                    let (len_specified, dynamic_len) = generate_dynamic_length(
-                        read,
-                        length_determining_field,
-                        dynamic_length_depth,
-                        quote! { _string },
-                    );
-
-                    if read {
-                        if len_specified {
+                "f32" | "f64" => match &attributes.float_encoding {
+                    None => {
+                        if read {
                             quote! {
-                                #dynamic_len
-                                let _string = &_p_bytes[*_p_pos..*_p_pos + _p_len];
-                                let _p_val = String::from_utf8(_string.to_vec()).expect("Invalid string");
-                                *_p_pos += _string.len();
-                                *_p_bits = 0; // A string should have full _p_bytes, and start with a full byte
+                                let _p_val = binary_codec::encodings::read_float(_p_reader, _p_config)?;
                             }
                         } else {
                             quote! {
-                                let _string = &_p_bytes[*_p_pos..];
-                                let _p_val = String::from_utf8(_string.to_vec()).expect("Invalid string");
-                                *_p_pos += _string.len();
-                                *_p_bits = 0; // A string should have full _p_bytes, and start with a full byte
+                                binary_codec::encodings::write_float(*_p_val, _p_writer, _p_config)?;
                             }
                         }
-                    } else {
-                        quote! {
-                            let _string = _p_val.as_bytes();
-                            #dynamic_len
-                            _p_bytes.extend_from_slice(_string);
-                            *_p_pos += _string.len();
-                            *_p_bits = 0;
-                        }
                     }
-                }
-                _ => {
-                    // Other types: try to call to_bytes() or from_bytes()
-                    // It is possible to have length determined
-                    let (len_specified, dynamic_len) = generate_dynamic_length(
-                        read,
-                        length_determining_field,
-                        dynamic_length_depth,
-                        quote! { _p_slice },
-                    );
-
-                    if read {
-                        let read_code = if let Some(variant_by) = variant_by_field {
-                            let variant_by = get_reference_accessor(variant_by, false);
-                            quote! {
-                                let _p_disc = #variant_by;
-                                let _p_val = #field_type::from_bytes_internal_with_disc(_p_disc, _p_slice, &mut _s_pos, _p_bits)?;
-                            }
-                        } else {
-                            quote! {
-                                let _p_val = #field_type::from_bytes_internal(_p_slice, &mut _s_pos, _p_bits)?;
-                            }
-                        };
-
-                        let handle = if len_specified {
+                    Some(FloatEncoding::Half) => {
+                        if read {
                             quote! {
-                                #dynamic_len
-                                let __s_pos = if *_p_bits != 0 && *_p_pos != 0 {
-                                    *_p_pos - 1
-                                } else {
-                                    *_p_pos
-                                };
-                                let _p_slice = &_p_bytes[__s_pos..__s_pos + _p_len];
+                                let _p_val = binary_codec::serializers::read_f16(_p_reader, _p_config)? as #ident;
                             }
                         } else {
                             quote! {
-                                let __s_pos = if *_p_bits != 0 && *_p_pos != 0 {
-                                    *_p_pos - 1
-                                } else {
-                                 *_p_pos
-                                };
-                                let _p_slice = &_p_bytes[__s_pos..];
+                                binary_codec::serializers::write_f16(*_p_val as f32, _p_writer, _p_config)?;
                             }
-                        };
-
-                        // It MIGHT be that the next objects reads bits from the last byte
-                        quote! {
-                            #handle
-                            let mut _s_pos = 0;
-                            #read_code
-                            *_p_pos += _s_pos;
                         }
-                    } else {
-                        if len_specified {
+                    }
+                    Some(FloatEncoding::FixedPoint { scale, bits, signed }) => {
+                        if read {
                             quote! {
-                                let mut _s_pos = 0;
-                                let mut _vec: Vec<u8> = Vec::new();
-                                _p_val.to_bytes_internal(&mut _vec, &mut _s_pos, _p_bits)?;
-                                let _p_slice = &_vec;
-                                #dynamic_len
-                                _p_bytes.extend_from_slice(_p_slice);
-                                *_p_pos += _s_pos;
+                                let _p_val = binary_codec::serializers::read_fixed_point(_p_reader, #scale, #bits, #signed, _p_config)? as #ident;
                             }
                         } else {
                             quote! {
-                                _p_val.to_bytes_internal(_p_bytes, _p_pos, _p_bits)?;
+                                binary_codec::serializers::write_fixed_point(*_p_val as f64, _p_writer, #scale, #bits, #signed, _p_config)?;
                             }
                         }
                     }
-                }
+                },
+                "String" => generate_byte_string(read, attributes, ByteString::String),
+                _ => generate_nested_object(read, field_type, attributes),
             }
         } else {
             // Multiple segments, or arguments
@@ -652,23 +1083,30 @@ fn generate_code_for_handling_field(
                 // );
 
                 match ident_name.as_ref() {
+                    "Cow" => {
+                        let inner_type = get_inner_type(path).expect("Cow missing inner type");
+                        let kind = match get_borrowed_kind(inner_type) {
+                            Some(ByteString::Str) => ByteString::CowStr,
+                            Some(ByteString::Bytes) => ByteString::CowBytes,
+                            _ => panic!("Cow field '{}' should be Cow<str> or Cow<[u8]>", field_name),
+                        };
+                        generate_byte_string(read, attributes, kind)
+                    }
                     "Option" => {
                         let inner_type = get_inner_type(path).expect("Option missing inner type");
                         let handle = generate_code_for_handling_field(
                             read,
                             inner_type,
                             field_name,
-                            bits_count_type,
-                            is_dynamic,
-                            dynamic_length_depth,
-                            length_determining_field,
-                            None,
-                            variant_by_field,
+                            &FieldAttributes {
+                                toggled_by_field: None,
+                                ..attributes.clone()
+                            },
                             level + 1,
                         );
                         let option_name: syn::Ident = format_ident!("__option_{}", level);
 
-                        if let Some(toggled_by) = toggled_by_field {
+                        if let Some(toggled_by) = attributes.toggled_by_field {
                             let toggled_by = get_reference_accessor(toggled_by, !read);
                             // If toggled_by is set, read or write it
                             if read {
@@ -682,9 +1120,14 @@ fn generate_code_for_handling_field(
                                 }
                             } else {
                                 quote! {
-                                    if #toggled_by {
-                                        let _p_val = _p_val.as_ref().expect("Expected Some value, because toggled_by field is true");
-                                        #handle
+                                    match (#toggled_by, _p_val.as_ref()) {
+                                        (true, Some(_p_val)) => {
+                                            #handle
+                                        }
+                                        (false, None) => {}
+                                        (_p_toggled, _) => {
+                                            return Err(binary_codec::SerializationErrorKind::ToggleMismatch(_p_toggled).into());
+                                        }
                                     }
                                 }
                             }
@@ -693,7 +1136,7 @@ fn generate_code_for_handling_field(
                             if read {
                                 quote! {
                                     let mut #option_name: Option<#inner_type> = None;
-                                    if *_p_pos < _p_bytes.len() {
+                                    if _p_reader.has_remaining()? {
                                         #handle
                                         #option_name = Some(_p_val);
                                     }
@@ -715,29 +1158,57 @@ fn generate_code_for_handling_field(
                             read,
                             inner_type,
                             field_name,
-                            bits_count_type,
-                            is_dynamic,
-                            dynamic_length_depth.map(|d| d - 1),
-                            None,
-                            None,
-                            None,
+                            &attributes.for_elements(attributes.dynamic_length_depth.and_then(|d| d.checked_sub(1))),
                             level + 1,
                         );
 
                         let (len_specified, dynamic_len) = generate_dynamic_length(
                             read,
-                            length_determining_field,
-                            dynamic_length_depth,
+                            attributes.length_determining_field,
+                            attributes.dynamic_length_depth,
                             quote! { _p_val },
                         );
 
+                        // Byte vectors are copied at once, which gives the same bytes as a u8 per element
+                        if is_byte_type(inner_type) && attributes.bits_count_type.is_none() {
+                            return if read {
+                                let bytes = if len_specified {
+                                    quote! { binary_codec::serializers::read_byte_slice(_p_reader, _p_len, _p_config)? }
+                                } else {
+                                    quote! { binary_codec::serializers::read_remaining(_p_reader, _p_config)? }
+                                };
+                                quote! {
+                                    #dynamic_len
+                                    let _p_val = #bytes.to_vec();
+                                }
+                            } else {
+                                quote! {
+                                    #dynamic_len
+                                    binary_codec::serializers::write_byte_slice(_p_val, _p_writer, _p_config)?;
+                                }
+                            };
+                        }
+
                         if read {
+                            let handle = generate_in_context(
+                                true,
+                                inner_type,
+                                handle,
+                                quote! { in_element(#vec_name.len(), _p_start.0, _p_start.1) },
+                            );
                             if len_specified {
                                 quote! {
                                     #dynamic_len
-                                    let mut #vec_name = Vec::<#inner_type>::with_capacity(_p_len);
-                                    for _ in 0.._p_len {
+                                    // Don't trust the length for the allocation, cap it by the bytes left
+                                    let mut #vec_name = Vec::<#inner_type>::with_capacity(
+                                        binary_codec::serializers::element_capacity::<#inner_type>(_p_len, _p_reader),
+                                    );
+                                    for _p_index in 0.._p_len {
+                                        let _p_before = (_p_reader.position(), _p_reader.bit_offset());
                                         #handle
+                                        if _p_index == 0 {
+                                            binary_codec::serializers::check_element_progress(_p_reader, _p_before, _p_len)?;
+                                        }
                                         #vec_name.push(_p_val);
                                     }
                                     let _p_val = #vec_name;
@@ -745,7 +1216,7 @@ fn generate_code_for_handling_field(
                             } else {
                                 quote! {
                                     let mut #vec_name = Vec::<#inner_type>::new();
-                                    while *_p_pos < _p_bytes.len() {
+                                    while _p_reader.has_remaining()? {
                                         #handle
                                         #vec_name.push(_p_val);
                                     }
@@ -753,9 +1224,10 @@ fn generate_code_for_handling_field(
                                 }
                             }
                         } else {
+                            let handle = generate_in_context(false, inner_type, handle, quote! { in_element(_p_index) });
                             quote! {
                                 #dynamic_len
-                                for _p_val in _p_val {
+                                for (_p_index, _p_val) in _p_val.iter().enumerate() {
                                     #handle
                                 }
                             }
@@ -768,12 +1240,7 @@ fn generate_code_for_handling_field(
                             read,
                             key_type,
                             field_name,
-                            bits_count_type,
-                            is_dynamic,
-                            dynamic_length_depth.map(|d| d - 1),
-                            None,
-                            None,
-                            None,
+                            &attributes.for_elements(attributes.dynamic_length_depth.and_then(|d| d.checked_sub(1))),
                             level + 1,
                         );
 
@@ -781,34 +1248,38 @@ fn generate_code_for_handling_field(
                             read,
                             value_type,
                             field_name,
-                            bits_count_type,
-                            is_dynamic,
-                            dynamic_length_depth.map(|d| d - 1),
-                            None,
-                            None,
-                            None,
+                            &attributes.for_elements(attributes.dynamic_length_depth.and_then(|d| d.checked_sub(1))),
                             level + 1,
                         );
 
                         let (len_specified, dynamic_len) = generate_dynamic_length(
                             read,
-                            length_determining_field,
-                            dynamic_length_depth,
+                            attributes.length_determining_field,
+                            attributes.dynamic_length_depth,
                             quote! { _p_val },
                         );
 
                         if read {
+                            let element = quote! { in_element(_p_map.len(), _p_start.0, _p_start.1) };
+                            let handle_key = generate_in_context(true, key_type, handle_key, element.clone());
+                            let handle_value = generate_in_context(true, value_type, handle_value, element);
                             if len_specified {
                                 quote! {
                                     #dynamic_len
-                                    let mut _p_map = std::collections::HashMap::<#key_type, #value_type>::with_capacity(_p_len);
-                                    for _ in 0.._p_len {
+                                    let mut _p_map = std::collections::HashMap::<#key_type, #value_type>::with_capacity(
+                                        binary_codec::serializers::element_capacity::<(#key_type, #value_type)>(_p_len, _p_reader),
+                                    );
+                                    for _p_index in 0.._p_len {
+                                        let _p_before = (_p_reader.position(), _p_reader.bit_offset());
                                         let _p_key;
                                         #handle_key
                                         _p_key = _p_val;
                                         let _p_value;
                                         #handle_value
                                         _p_value = _p_val;
+                                        if _p_index == 0 {
+                                            binary_codec::serializers::check_element_progress(_p_reader, _p_before, _p_len)?;
+                                        }
                                         _p_map.insert(_p_key, _p_value);
                                     }
                                     let _p_val = _p_map;
@@ -816,7 +1287,7 @@ fn generate_code_for_handling_field(
                             } else {
                                 quote! {
                                     let mut _p_map = std::collections::HashMap::<#key_type, #value_type>::new();
-                                    while *_p_pos < _p_bytes.len() {
+                                    while _p_reader.has_remaining()? {
                                         let _p_key;
                                         #handle_key
                                         _p_key = _p_val;
@@ -829,9 +1300,12 @@ fn generate_code_for_handling_field(
                                 }
                             }
                         } else {
+                            let handle_key = generate_in_context(false, key_type, handle_key, quote! { in_element(_p_index) });
+                            let handle_value =
+                                generate_in_context(false, value_type, handle_value, quote! { in_element(_p_index) });
                             quote! {
                                 #dynamic_len
-                                for (key, value) in _p_val {
+                                for (_p_index, (key, value)) in _p_val.iter().enumerate() {
                                     let _p_val = key;
                                     #handle_key
                                     let _p_val = value;
@@ -840,12 +1314,11 @@ fn generate_code_for_handling_field(
                             }
                         }
                     }
-                    _ => {
-                        panic!("Type not implemented")
-                    }
+                    // Other types with generic arguments, like Frame<'a> or Packet<T>
+                    _ => generate_nested_object(read, field_type, attributes),
                 }
             } else {
-                panic!("Multi-segment paths are not supported");
+                generate_nested_object(read, field_type, attributes)
             }
         }
     } else if let Type::Array(array) = field_type {
@@ -864,42 +1337,281 @@ fn generate_code_for_handling_field(
         // println!("Found array '{:?}' with length: {}", field_name, len);
 
         let array_type = &array.elem;
+
+        // Byte arrays are copied at once, see Vec
+        if is_byte_type(array_type) && attributes.bits_count_type.is_none() {
+            return if read {
+                quote! {
+                    let _p_slice = binary_codec::serializers::read_byte_slice(_p_reader, #len, _p_config)?;
+                    let _p_val = <[u8; #len]>::try_from(_p_slice)
+                        .map_err(|_| binary_codec::DeserializationErrorKind::UnexpectedLength(#len, _p_slice.len()))?;
+                }
+            } else {
+                quote! {
+                    binary_codec::serializers::write_byte_slice(_p_val, _p_writer, _p_config)?;
+                }
+            };
+        }
+
         let handle = generate_code_for_handling_field(
             read,
             array_type,
             field_name,
-            bits_count_type,
-            is_dynamic,
-            dynamic_length_depth,
-            None,
-            None,
-            None,
+            &attributes.for_elements(attributes.dynamic_length_depth),
             level + 1,
         );
 
         let array_name = format_ident!("__val_{}", level);
 
         if read {
+            let handle = generate_in_context(
+                true,
+                array_type,
+                handle,
+                quote! { in_element(#array_name.len(), _p_start.0, _p_start.1) },
+            );
             quote! {
                 let mut #array_name = Vec::<#array_type>::with_capacity(#len);
                 for _ in 0..#len {
-                    #handle;
+                    #handle
                     #array_name.push(_p_val);
                 }
-                let _p_val = TryInto::<[#array_type; #len]>::try_into(#array_name).expect("Failed to convert Vec to array");
+                let _p_val = TryInto::<[#array_type; #len]>::try_into(#array_name)
+                    .map_err(|v: Vec<#array_type>| binary_codec::DeserializationErrorKind::UnexpectedLength(#len, v.len()))?;
             }
         } else {
+            let handle = generate_in_context(false, array_type, handle, quote! { in_element(_p_index) });
             quote! {
-                for _p_val in _p_val {
+                for (_p_index, _p_val) in _p_val.iter().enumerate() {
                     #handle
                 }
             }
         }
+    } else if let Type::Reference(reference) = field_type {
+        let kind = get_borrowed_kind(&reference.elem)
+            .unwrap_or_else(|| panic!("Reference field '{}' should be &str or &[u8]", field_name));
+        generate_byte_string(read, attributes, kind)
     } else {
         panic!("Field type of '{:?}' not supported", field_name);
     }
 }
 
+/// Types that implement ToBytes / FromBytes themselves, optionally length delimited
+fn generate_nested_object(read: bool, field_type: &Type, attributes: &FieldAttributes) -> proc_macro2::TokenStream {
+    // Other types: try to call to_bytes() or from_bytes()
+    // It is possible to have length determined
+    let (len_specified, dynamic_len) = generate_dynamic_length(
+        read,
+        attributes.length_determining_field,
+        attributes.dynamic_length_depth,
+        quote! { _p_slice },
+    );
+
+    if read {
+        let read_code = if let Some(variant_by) = attributes.variant_by_field {
+            let variant_by = get_reference_accessor(variant_by, false);
+            quote! {
+                let _p_disc = #variant_by as _;
+                let _p_val = <#field_type>::from_bytes_internal_with_disc(_p_disc, _p_reader, _p_config)?;
+            }
+        } else {
+            quote! {
+                let _p_val = <#field_type as binary_codec::FromBytes>::from_bytes_internal(_p_reader, _p_config)?;
+            }
+        };
+
+        // A length delimited object starts at a full byte and gets a reader over exactly its bytes,
+        // which moves offsets in errors to the start of the object
+        if attributes.length_determining_field.is_some() {
+            quote! {
+                #dynamic_len
+                let _p_val = _p_reader.read_delimited(_p_len, |_p_reader| {
+                    #read_code
+                    Ok(_p_val)
+                })?;
+            }
+        } else if len_specified {
+            quote! {
+                let _p_val = _p_reader.read_length_prefixed(_p_config, |_p_reader| {
+                    #read_code
+                    Ok(_p_val)
+                })?;
+            }
+        } else {
+            // It MIGHT be that the next objects reads bits from the last byte, so continue in the same buffer
+            read_code
+        }
+    } else {
+        let write_code = quote! { |_p_writer| binary_codec::ToBytes::to_bytes_internal(_p_val, _p_writer, _p_config) };
+        if attributes.length_determining_field.is_some() {
+            // Write in place, then check the length of the written bytes
+            quote! {
+                let _p_slice = _p_writer.write_delimited(#write_code)?;
+                #dynamic_len
+            }
+        } else if len_specified {
+            // The length prefix is reserved and filled in after writing, no temporary buffer needed
+            quote! {
+                _p_writer.write_length_prefixed(_p_config, #write_code)?;
+            }
+        } else {
+            quote! {
+                binary_codec::ToBytes::to_bytes_internal(_p_val, _p_writer, _p_config)?;
+            }
+        }
+    }
+}
+
+/// Types that are written as their bytes, with an optional length prefix
+enum ByteString {
+    String,
+    Str,
+    Bytes,
+    CowStr,
+    CowBytes,
+}
+
+/// `str` and `[u8]`, the types that can be borrowed from the input
+fn get_borrowed_kind(ty: &Type) -> Option<ByteString> {
+    match ty {
+        Type::Path(path) if path.path.is_ident("str") => Some(ByteString::Str),
+        Type::Slice(slice) if is_byte_type(&slice.elem) => Some(ByteString::Bytes),
+        _ => None,
+    }
+}
+
+fn is_byte_type(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("u8"))
+}
+
+/**
+ * Read and write String, &str, &[u8] and Cow<str> / Cow<[u8]>. The length is determined by:
+ * 1. the length_determining_field, cast to usize
+ * 2. the length prefix, when #[dynamic_len] is set
+ * 3. otherwise the bytes left
+ *
+ * Borrowed types borrow the bytes from the input instead of copying them.
+ */
+fn generate_byte_string(read: bool, attributes: &FieldAttributes, kind: ByteString) -> proc_macro2::TokenStream {
+    let (len_specified, dynamic_len) = generate_dynamic_length(
+        read,
+        attributes.length_determining_field,
+        attributes.dynamic_length_depth,
+        quote! { _string },
+    );
+
+    if read {
+        let read_string = if len_specified {
+            quote! {
+                #dynamic_len
+                // A string should have full bytes, and start with a full byte
+                let _string = binary_codec::serializers::read_bytes(_p_reader, _p_len, _p_config)?;
+            }
+        } else {
+            quote! {
+                let _string = binary_codec::serializers::read_remaining(_p_reader, _p_config)?;
+            }
+        };
+
+        let utf8 = quote! { map_err(|_| binary_codec::DeserializationErrorKind::InvalidUtf8)? };
+        let convert = match kind {
+            ByteString::String => quote! { String::from_utf8(_string.to_vec()).#utf8 },
+            ByteString::Str => quote! { std::str::from_utf8(_string).#utf8 },
+            ByteString::Bytes => quote! { _string },
+            ByteString::CowStr => quote! { std::borrow::Cow::Borrowed(std::str::from_utf8(_string).#utf8) },
+            ByteString::CowBytes => quote! { std::borrow::Cow::Borrowed(_string) },
+        };
+
+        quote! {
+            #read_string
+            let _p_val = #convert;
+        }
+    } else {
+        let as_bytes = match kind {
+            ByteString::String | ByteString::Str | ByteString::CowStr => quote! { _p_val.as_bytes() },
+            ByteString::Bytes | ByteString::CowBytes => quote! { _p_val },
+        };
+
+        quote! {
+            let _string: &[u8] = #as_bytes;
+            #dynamic_len
+            _p_writer.write_bytes(_string)?;
+        }
+    }
+}
+
+/**
+ * Add the trait bounds for the generated impl. By default every type parameter gets a
+ * `T: ToBytes` or `T: FromBytes<'_p_de>` bound. This can be overridden with #[bound = "T: MyTrait"] on the container
+ * for both impls, or per impl with #[bound(to_bytes = "...", from_bytes = "...")]. An empty string means no extra bounds.
+ * When reading, the lifetime '_p_de of the input is added, which outlives all lifetimes of the type.
+ */
+fn generate_generics(read: bool, ast: &DeriveInput) -> syn::Generics {
+    let mut generics = ast.generics.clone();
+
+    if read {
+        let mut input_lifetime =
+            syn::LifetimeParam::new(syn::Lifetime::new("'_p_de", proc_macro2::Span::call_site()));
+        input_lifetime.bounds.extend(ast.generics.lifetimes().map(|param| param.lifetime.clone()));
+        generics.params.insert(0, syn::GenericParam::Lifetime(input_lifetime));
+    }
+
+    let explicit_bound = ast
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("bound"))
+        .and_then(|attr| get_bound_from_attribute(read, attr, &ast.ident));
+
+    let predicates: Vec<syn::WherePredicate> = if let Some(bound) = explicit_bound {
+        let parsed = syn::parse::Parser::parse_str(
+            Punctuated::<syn::WherePredicate, Comma>::parse_terminated,
+            &bound,
+        )
+        .unwrap_or_else(|e| panic!("Invalid bound '{}' above '{}': {}", bound, ast.ident, e));
+        parsed.into_iter().collect()
+    } else {
+        let codec_trait = if read {
+            quote! { binary_codec::FromBytes<'_p_de> }
+        } else {
+            quote! { binary_codec::ToBytes }
+        };
+
+        ast.generics
+            .type_params()
+            .map(|param| {
+                let ident = &param.ident;
+                syn::parse_quote! { #ident: #codec_trait }
+            })
+            .collect()
+    };
+
+    generics.make_where_clause().predicates.extend(predicates);
+    generics
+}
+
+/// The bound of `#[bound = "..."]`, or the one for this impl of `#[bound(to_bytes = "...", from_bytes = "...")]`
+fn get_bound_from_attribute(read: bool, attr: &Attribute, container_name: &syn::Ident) -> Option<String> {
+    if !matches!(attr.meta, syn::Meta::List(_)) {
+        return Some(get_string_value_from_attribute("bound", attr, container_name));
+    }
+
+    let mut to_bytes = None;
+    let mut from_bytes = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("to_bytes") {
+            to_bytes = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else if meta.path.is_ident("from_bytes") {
+            from_bytes = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+        } else {
+            return Err(meta.error("expected to_bytes or from_bytes"));
+        }
+        Ok(())
+    })
+    .unwrap_or_else(|e| panic!("Invalid bound attribute above '{}': {}", container_name, e));
+
+    if read { from_bytes } else { to_bytes }
+}
+
 fn generate_error_type(read: bool) -> proc_macro2::TokenStream {
     if read {
         quote! { binary_codec::DeserializationError }
@@ -937,11 +1649,15 @@ fn get_string_value_from_attribute(
     }
 }
 
-fn get_int_value_from_attribute(
+fn get_int_value_from_attribute<N>(
     attribute_name: &str,
     attr: &Attribute,
     field_name: &syn::Ident,
-) -> i32 {
+) -> N
+where
+    N: std::str::FromStr,
+    N::Err: std::fmt::Display,
+{
     if let syn::Meta::NameValue(name_value) = &attr.meta {
         if let syn::Expr::Lit(lit_expr) = &name_value.value {
             if let Lit::Int(lit_str) = &lit_expr.lit {
@@ -966,6 +1682,72 @@ fn get_int_value_from_attribute(
     }
 }
 
+fn get_fixed_point_from_attribute(attr: &Attribute, field_name: &syn::Ident) -> FloatEncoding {
+    let mut scale = None;
+    let mut bits = None;
+    let mut signed = true;
+
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("scale") {
+            scale = Some(match meta.value()?.parse::<Lit>()? {
+                Lit::Int(lit) => lit.base10_parse::<f64>()?,
+                Lit::Float(lit) => lit.base10_parse::<f64>()?,
+                _ => return Err(meta.error("scale should be a number")),
+            });
+        } else if meta.path.is_ident("bits") {
+            bits = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse::<u8>()?);
+        } else if meta.path.is_ident("unsigned") {
+            signed = false;
+        } else {
+            return Err(meta.error("expected scale, bits or unsigned"));
+        }
+        Ok(())
+    })
+    .unwrap_or_else(|e| panic!("Invalid fixed_point attribute on '{}': {}", field_name, e));
+
+    let scale = scale.unwrap_or_else(|| panic!("Missing scale in fixed_point attribute on '{}'", field_name));
+    let bits = bits.unwrap_or_else(|| panic!("Missing bits in fixed_point attribute on '{}'", field_name));
+
+    if scale <= 0.0 {
+        panic!("fixed_point scale should be positive");
+    }
+
+    if !(1..=64).contains(&bits) {
+        panic!("fixed_point bits should be between 1 and 64");
+    }
+
+    FloatEncoding::FixedPoint { scale, bits, signed }
+}
+
+fn get_reserved_from_attribute(attr: &Attribute, field_name: &syn::Ident) -> (u8, u128) {
+    let mut bits = None;
+    let mut value = 0u128;
+
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("bits") {
+            bits = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse::<u8>()?);
+        } else if meta.path.is_ident("value") {
+            value = meta.value()?.parse::<syn::LitInt>()?.base10_parse::<u128>()?;
+        } else {
+            return Err(meta.error("expected bits or value"));
+        }
+        Ok(())
+    })
+    .unwrap_or_else(|e| panic!("Invalid reserved attribute on '{}': {}", field_name, e));
+
+    let bits = bits.unwrap_or_else(|| panic!("Missing bits in reserved attribute on '{}'", field_name));
+
+    if !(1..=128).contains(&bits) {
+        panic!("reserved bits of '{}' should be between 1 and 128", field_name);
+    }
+
+    if bits < 128 && value >> bits != 0 {
+        panic!("reserved value of '{}' doesn't fit in {} bits", field_name, bits);
+    }
+
+    (bits, value)
+}
+
 fn get_int_value_from_attribute_2(attr: &Attribute) -> Option<usize> {
     match &attr.meta {
         syn::Meta::Path(_) => {
@@ -974,20 +1756,20 @@ fn get_int_value_from_attribute_2(attr: &Attribute) -> Option<usize> {
         syn::Meta::List(list_value) => {
             // #[dynamic_len(value)]
             for token in list_value.tokens.clone().into_iter() {
-                if let proc_macro2::TokenTree::Literal(lit) = token {
-                    if let Ok(val) = lit.to_string().parse::<usize>() {
-                        return Some(val);
-                    }
+                if let proc_macro2::TokenTree::Literal(lit) = token
+                    && let Ok(val) = lit.to_string().parse::<usize>()
+                {
+                    return Some(val);
                 }
             }
 
             None
         }
         syn::Meta::NameValue(name_value) => {
-            if let syn::Expr::Lit(lit_expr) = &name_value.value {
-                if let Lit::Int(lit_int) = &lit_expr.lit {
-                    return Some(lit_int.base10_parse().expect("Not a valid int value"));
-                }
+            if let syn::Expr::Lit(lit_expr) = &name_value.value
+                && let Lit::Int(lit_int) = &lit_expr.lit
+            {
+                return Some(lit_int.base10_parse().expect("Not a valid int value"));
             }
 
             None
@@ -998,9 +1780,10 @@ fn get_int_value_from_attribute_2(attr: &Attribute) -> Option<usize> {
 fn get_field_name_from_attribute<'a>(
     attribute_name: &str,
     attr: &Attribute,
-    fields: &'a Fields,
+    fields: &Fields,
+    idents: &'a [syn::Ident],
     field_name: &syn::Ident,
-) -> (&'a syn::Ident, Option<i32>) {
+) -> FieldReference<'a> {
     let mut field_name = get_string_value_from_attribute(attribute_name, attr, field_name);
     let mut index: Option<i32> = None;
 
@@ -1025,28 +1808,26 @@ fn get_field_name_from_attribute<'a>(
         field_name
     };
 
-    let determining_field = fields
-        .iter()
-        .find(|f| f.ident.as_ref().is_some_and(|i| i == &field_name))
-        .expect(&format!("Referenced field '{}' not found", field_name));
-
-    let field = determining_field.ident.as_ref().expect(&format!(
-        "Referenced field '{}' has no name, which is not supported",
-        field_name
-    ));
+    // Fields are referenced by name, or by position for tuple fields
+    let position = if let Ok(position) = field_name.parse::<usize>() {
+        (position < idents.len()).then_some(position)
+    } else {
+        fields
+            .iter()
+            .position(|f| f.ident.as_ref().is_some_and(|i| i == &field_name))
+    }
+    .unwrap_or_else(|| panic!("Referenced field '{}' not found", field_name));
 
-    (field, index)
+    (&idents[position], index)
 }
 
+/// First type argument of a path, lifetimes are skipped (`Cow<'a, str>` gives `str`)
 fn get_inner_type(path: &syn::Path) -> Option<&syn::Type> {
-    if let Some(PathArguments::AngleBracketed(args)) =
-        path.segments.last().map(|seg| &seg.arguments)
-    {
-        if let Some(arg) = args.args.first() {
-            if let syn::GenericArgument::Type(inner_type) = arg {
-                return Some(inner_type);
-            }
-        }
+    if let Some(PathArguments::AngleBracketed(args)) = path.segments.last().map(|seg| &seg.arguments) {
+        return args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(inner_type) => Some(inner_type),
+            _ => None,
+        });
     }
 
     None
@@ -1072,25 +1853,28 @@ fn get_two_types(path: &syn::Path) -> Option<(&syn::Type, &syn::Type)> {
     None
 }
 
-fn get_reference_accessor(field_reference: FieldReference, is_self: bool) -> proc_macro2::TokenStream {
+/// Access a referenced field. When writing, the fields are references to the fields of self.
+/// An index past the end of the referenced field is an error, not a panic
+fn get_reference_accessor(field_reference: FieldReference, is_ref: bool) -> proc_macro2::TokenStream {
     let name = field_reference.0;
     if let Some(index) = field_reference.1 {
         if index == -1 {
-            if is_self {
-                quote! { !self.#name }
+            if is_ref {
+                quote! { !*#name }
             } else {
                 quote! { !#name }
             }
         } else {
             let index = index as usize;
-            if is_self {
-                quote! { self.#name[#index] }
+            let error_kind = if is_ref {
+                quote! { binary_codec::SerializationErrorKind }
             } else {
-                quote! { #name[#index] }
-            }
+                quote! { binary_codec::DeserializationErrorKind }
+            };
+            quote! { (*#name.get(#index).ok_or(#error_kind::ReferenceOutOfBounds(#index, #name.len()))?) }
         }
-    } else if is_self {
-        quote! { self.#name }
+    } else if is_ref {
+        quote! { (*#name) }
     } else {
         quote! { #name }
     }
@@ -1099,22 +1883,17 @@ fn get_reference_accessor(field_reference: FieldReference, is_self: bool) -> pro
 fn generate_dynint(read: bool) -> proc_macro2::TokenStream {
     if read {
         quote! {
-            let (_p_dyn, _bytes_read) = binary_codec::dyn_int::read_from_slice(&_p_bytes[*_p_pos..])?;
-            *_p_pos += _bytes_read;
-            *_p_bits = 0;
+            let _p_dyn = binary_codec::serializers::read_dyn_int(_p_reader, _p_config)?;
         }
     } else {
         quote! {
-            let _p_enc = binary_codec::dyn_int::encode(_p_dyn);
-            _p_bytes.extend_from_slice(&_p_enc);
-            *_p_pos += _p_enc.len();
-            *_p_bits = 0;
+            _p_writer.write_dyn_int(_p_dyn)?;
         }
     }
 }
 
 /**
- * Generate code writing or reading dynamic integer, or reading and validating length determining field in struct
+ * Generate code writing or reading the length prefix, or reading and validating length determining field in struct
  * If the length is specified this produces:
  * read:
  * let _p_len : usize = ...;
@@ -1125,53 +1904,44 @@ fn generate_dynamic_length(
     dynamic_length_depth: Option<usize>,
     item: proc_macro2::TokenStream,
 ) -> (bool, proc_macro2::TokenStream) {
-    let dynint = generate_dynint(read);
     if read {
         if let Some(length_determining_field) = length_determining_field {
             let length_determining_field = get_reference_accessor(length_determining_field, false);
             (
                 true,
                 quote! {
-                    let _p_len = #length_determining_field as usize;
+                    let _p_len = binary_codec::serializers::check_length(#length_determining_field as usize, _p_config)?;
                 },
             )
-        } else {
-            if dynamic_length_depth.is_some_and(|v| v > 0) {
-                (
-                    true,
-                    quote! {
-                        #dynint
-                        let _p_len = _p_dyn as usize;
-                    },
-                )
-            } else {
-                (false, quote! {})
-            }
-        }
-    } else {
-        if let Some(length_determining_field) = length_determining_field {
-            let length_determining_field = get_reference_accessor(length_determining_field, true);
+        } else if dynamic_length_depth.is_some_and(|v| v > 0) {
             (
                 true,
                 quote! {
-                    let expected_len = #length_determining_field as usize;
-                    if #item.len() != expected_len {
-                        return Err(binary_codec::SerializationError::UnexpectedLength(expected_len, #item.len()));
-                    }
+                    let _p_len = binary_codec::serializers::read_length(_p_reader, _p_config)?;
                 },
             )
         } else {
-            if dynamic_length_depth.is_some_and(|v| v > 0) {
-                (
-                    true,
-                    quote! {
-                        let _p_dyn = #item.len() as u128;
-                        #dynint
-                    },
-                )
-            } else {
-                (false, quote! {})
-            }
+            (false, quote! {})
         }
+    } else if let Some(length_determining_field) = length_determining_field {
+        let length_determining_field = get_reference_accessor(length_determining_field, true);
+        (
+            true,
+            quote! {
+                let expected_len = #length_determining_field as usize;
+                if #item.len() != expected_len {
+                    return Err(binary_codec::SerializationErrorKind::UnexpectedLength(expected_len, #item.len()).into());
+                }
+            },
+        )
+    } else if dynamic_length_depth.is_some_and(|v| v > 0) {
+        (
+            true,
+            quote! {
+                binary_codec::serializers::write_length(#item.len(), _p_writer, _p_config)?;
+            },
+        )
+    } else {
+        (false, quote! {})
     }
 }
@@ -0,0 +1,74 @@
+//! Encoding and decoding of multi-megabyte byte payloads. `Blob` uses the bulk copy of `Vec<u8>`,
+//! `BitBlob` forces the per element path with `#[bits = 8]`, which produces the same bytes.
+
+use binary_codec::{FromBytes, SerializationConfig, ToBytes};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+#[derive(ToBytes, FromBytes)]
+struct Blob {
+    id: u32,
+    #[dynamic_len]
+    data: Vec<u8>,
+}
+
+#[derive(ToBytes, FromBytes)]
+struct BitBlob {
+    id: u32,
+    #[dynamic_len]
+    #[bits = 8]
+    data: Vec<u8>,
+}
+
+#[derive(ToBytes, FromBytes)]
+struct Chunks {
+    #[dynamic_len]
+    chunks: Vec<[u8; 4096]>,
+}
+
+const SIZES: [usize; 2] = [1 << 20, 4 << 20];
+
+fn payload(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn bench_write(c: &mut Criterion) {
+    let config = SerializationConfig::default();
+    let mut group = c.benchmark_group("write");
+    for size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        let blob = Blob { id: 1, data: payload(size) };
+        let bit_blob = BitBlob { id: 1, data: payload(size) };
+        group.bench_with_input(BenchmarkId::new("vec_u8", size), &blob, |b, blob| {
+            b.iter(|| black_box(blob.to_bytes(&config).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("per_element", size), &bit_blob, |b, blob| {
+            b.iter(|| black_box(blob.to_bytes(&config).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_read(c: &mut Criterion) {
+    let config = SerializationConfig::default();
+    let mut group = c.benchmark_group("read");
+    for size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        let bytes = Blob { id: 1, data: payload(size) }.to_bytes(&config).unwrap();
+        group.bench_with_input(BenchmarkId::new("vec_u8", size), &bytes, |b, bytes| {
+            b.iter(|| black_box(Blob::from_bytes(bytes, &config).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("per_element", size), &bytes, |b, bytes| {
+            b.iter(|| black_box(BitBlob::from_bytes(bytes, &config).unwrap()))
+        });
+
+        let chunks = Chunks { chunks: payload(size).chunks(4096).map(|c| c.try_into().unwrap()).collect() };
+        let bytes = chunks.to_bytes(&config).unwrap();
+        group.bench_with_input(BenchmarkId::new("u8_arrays", size), &bytes, |b, bytes| {
+            b.iter(|| black_box(Chunks::from_bytes(bytes, &config).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_write, bench_read);
+criterion_main!(benches);
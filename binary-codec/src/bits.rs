@@ -0,0 +1,548 @@
+//! Bit level writer and reader, the cursor every `ToBytes` / `FromBytes` implementation works with.
+//!
+//! Bit fields share bytes (see `BitPacking` and `BitOrder`), byte values like fixed width integers,
+//! dynamic integers and byte strings start at a new byte. The writer and reader keep track of the
+//! byte and bit position, so implementations only call the methods for the values they contain.
+
+use std::cell::OnceCell;
+use std::io::{self, Read};
+
+use crate::{
+    dyn_int,
+    encodings::{self, FixedInt, ZigZag},
+    serializers, DeserializationError, DeserializationErrorKind, SerializationConfig, SerializationError,
+    SerializationErrorKind,
+};
+
+/// Writes values into a byte vector, or into a fixed size slice without allocating
+pub struct BitWriter<'a> {
+    buffer: Buffer<'a>,
+    /// Length of the buffer when the writer (or the current delimited value) started, positions are relative to it
+    start: usize,
+    /// Bits used of the last byte, 0 when the next bit field starts a new byte
+    pub(crate) bits: u8,
+}
+
+impl<'a> BitWriter<'a> {
+    /// Writer appending to `bytes`
+    pub fn new(bytes: &'a mut Vec<u8>) -> Self {
+        let start = bytes.len();
+        Self { buffer: Buffer::Vec(bytes), start, bits: 0 }
+    }
+
+    /// Writer filling `buf` from the start. Writing past its end fails with `BufferTooSmall`
+    pub fn from_slice(buf: &'a mut [u8]) -> Self {
+        Self { buffer: Buffer::Slice { buf, len: 0 }, start: 0, bits: 0 }
+    }
+
+    /// Number of bytes written, including a partially filled last byte
+    pub fn position(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    /// Number of bits used of the last byte, 0 at a byte boundary
+    pub fn bit_offset(&self) -> u8 {
+        self.bits
+    }
+
+    /// The bytes written so far
+    pub fn written(&self) -> &[u8] {
+        &self.buffer.filled()[self.start..]
+    }
+
+    /// Finish the current byte, the next bit field starts at a new byte
+    pub fn align(&mut self) {
+        self.bits = 0;
+    }
+
+    /// Write an unsigned value of `bit_count` bits (1 to 128, otherwise `InvalidBitCount`)
+    pub fn write_bits(&mut self, val: u128, bit_count: u8, config: &SerializationConfig) -> Result<(), SerializationError> {
+        serializers::write_bits_unsigned(val, self, bit_count, config)
+    }
+
+    /// Write a signed value of `bit_count` bits (1 to 128, otherwise `InvalidBitCount`), zigzag encoded
+    pub fn write_signed_bits(
+        &mut self,
+        val: i128,
+        bit_count: u8,
+        config: &SerializationConfig,
+    ) -> Result<(), SerializationError> {
+        serializers::write_bits_signed(val, self, bit_count, config)
+    }
+
+    /// Write a bool as a single bit
+    pub fn write_bool(&mut self, val: bool, config: &SerializationConfig) -> Result<(), SerializationError> {
+        serializers::write_bool(val, self, config)
+    }
+
+    /// Write a fixed width integer in the configured byte order, starting at a new byte
+    pub fn write_fixed<T: FixedInt<S>, const S: usize>(
+        &mut self,
+        val: T,
+        config: &SerializationConfig,
+    ) -> Result<(), SerializationError> {
+        val.write(self, config)
+    }
+
+    /// Write a signed integer zigzag encoded as fixed width integer, starting at a new byte
+    pub fn write_zigzag<T, const S: usize>(&mut self, val: T, config: &SerializationConfig) -> Result<(), SerializationError>
+    where
+        T: ZigZag,
+        T::Unsigned: FixedInt<S>,
+    {
+        encodings::write_zigzag(val, self, config)
+    }
+
+    /// Write a dynamic integer (varint), starting at a new byte
+    pub fn write_dyn_int(&mut self, val: u128) -> Result<(), SerializationError> {
+        let mut encoded = [0; dyn_int::MAX_ENCODED_SIZE];
+        let len = dyn_int::encode_into(val, &mut encoded);
+        self.write_bytes(&encoded[..len])
+    }
+
+    /// Write bytes, starting at a new byte
+    pub fn write_bytes(&mut self, val: &[u8]) -> Result<(), SerializationError> {
+        self.bits = 0;
+        self.buffer.extend(val)
+    }
+
+    /// Write a length prefix using the configured length prefix strategy
+    pub fn write_length(&mut self, len: usize, config: &SerializationConfig) -> Result<(), SerializationError> {
+        serializers::write_length(len, self, config)
+    }
+
+    /// Write a value that occupies whole bytes, starting at a new byte. Positions (like `#[align]`) are
+    /// relative to the start of the value. Returns the bytes of the value
+    pub fn write_delimited(
+        &mut self,
+        write: impl FnOnce(&mut Self) -> Result<(), SerializationError>,
+    ) -> Result<&[u8], SerializationError> {
+        self.bits = 0;
+        let value_start = self.buffer.len();
+        let outer_start = std::mem::replace(&mut self.start, value_start);
+        let result = write(self);
+        self.start = outer_start;
+        self.bits = 0;
+        result?;
+        Ok(&self.buffer.filled()[value_start..])
+    }
+
+    /// Write a value with a length prefix (`config.length_prefix`), like `write_delimited`. Space for the prefix
+    /// is reserved and filled in afterwards, so the value is written in place instead of in a temporary buffer.
+    /// A dynamic prefix reserves one byte, a longer value is moved to make room for the prefix
+    pub fn write_length_prefixed(
+        &mut self,
+        config: &SerializationConfig,
+        write: impl FnOnce(&mut Self) -> Result<(), SerializationError>,
+    ) -> Result<(), SerializationError> {
+        self.bits = 0;
+        let prefix_start = self.buffer.len();
+        let reserved = serializers::length_prefix_size(0, config);
+        self.buffer.grow(reserved)?;
+
+        let len = self.write_delimited(write)?.len();
+        let prefix_len = serializers::length_prefix_size(len, config);
+        if prefix_len > reserved {
+            self.buffer.insert(prefix_start + reserved, prefix_len - reserved)?;
+        }
+
+        let prefix = &mut self.buffer.filled_mut()[prefix_start..prefix_start + prefix_len];
+        serializers::write_length(len, &mut BitWriter::from_slice(prefix), config)
+    }
+
+    /// Append `count` zero bytes
+    pub(crate) fn write_zeros(&mut self, count: usize) -> Result<(), SerializationError> {
+        self.bits = 0;
+        self.buffer.grow(count)
+    }
+
+    /// Start a new byte for the next bits
+    pub(crate) fn push_byte(&mut self) -> Result<(), SerializationError> {
+        self.buffer.grow(1)
+    }
+
+    /// The byte the current bits are written to
+    pub(crate) fn last_byte(&mut self) -> &mut u8 {
+        let bytes = self.buffer.filled_mut();
+        let last = bytes.len() - 1;
+        &mut bytes[last]
+    }
+}
+
+/// Storage of a `BitWriter`
+enum Buffer<'a> {
+    Vec(&'a mut Vec<u8>),
+    /// Fixed size slice, of which the first `len` bytes are written
+    Slice { buf: &'a mut [u8], len: usize },
+}
+
+impl Buffer<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Buffer::Vec(bytes) => bytes.len(),
+            Buffer::Slice { len, .. } => *len,
+        }
+    }
+
+    fn filled(&self) -> &[u8] {
+        match self {
+            Buffer::Vec(bytes) => bytes,
+            Buffer::Slice { buf, len } => &buf[..*len],
+        }
+    }
+
+    fn filled_mut(&mut self) -> &mut [u8] {
+        match self {
+            Buffer::Vec(bytes) => bytes,
+            Buffer::Slice { buf, len } => &mut buf[..*len],
+        }
+    }
+
+    /// Fail if a slice has no room for `count` more bytes
+    fn check_capacity(buf: &[u8], len: usize, count: usize) -> Result<(), SerializationError> {
+        if count > buf.len() - len {
+            return Err(SerializationErrorKind::BufferTooSmall(buf.len()).into());
+        }
+        Ok(())
+    }
+
+    fn extend(&mut self, val: &[u8]) -> Result<(), SerializationError> {
+        match self {
+            Buffer::Vec(bytes) => bytes.extend_from_slice(val),
+            Buffer::Slice { buf, len } => {
+                Self::check_capacity(buf, *len, val.len())?;
+                buf[*len..*len + val.len()].copy_from_slice(val);
+                *len += val.len();
+            }
+        }
+        Ok(())
+    }
+
+    /// Append `count` zero bytes
+    fn grow(&mut self, count: usize) -> Result<(), SerializationError> {
+        match self {
+            Buffer::Vec(bytes) => bytes.resize(bytes.len() + count, 0),
+            Buffer::Slice { buf, len } => {
+                Self::check_capacity(buf, *len, count)?;
+                buf[*len..*len + count].fill(0);
+                *len += count;
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert `count` bytes at `at`, moving the bytes after it
+    fn insert(&mut self, at: usize, count: usize) -> Result<(), SerializationError> {
+        let end = self.len();
+        self.grow(count)?;
+        self.filled_mut().copy_within(at..end, at + count);
+        Ok(())
+    }
+}
+
+/// Reads values from a byte slice, or from a stream (see `FromBytesOwned::read_from`).
+/// Values that borrow from the input (like `&str`) live as long as the slice
+pub struct BitReader<'a> {
+    input: Input<'a>,
+    /// Number of bytes started, counted from `base`
+    pos: usize,
+    /// Bits used of the last started byte, 0 when the next bit field starts a new byte
+    pub(crate) bits: u8,
+    /// Position of the first buffered byte, a stream drops the bytes it doesn't need anymore
+    base: usize,
+    end: End,
+}
+
+/// What the end of the bytes of a reader means, for values that read until it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum End {
+    /// The end of the input
+    Input,
+    /// More bytes may follow (`try_from_bytes`), the end is not known yet
+    Open,
+    /// A stream of values, every value has to know its own length
+    Stream,
+}
+
+enum Input<'a> {
+    Slice(&'a [u8]),
+    Stream(Stream<'a>),
+}
+
+/// Bytes read from a `Read`, exactly as many as the values need
+struct Stream<'a> {
+    source: &'a mut dyn Read,
+    /// Bytes read that are still needed: the last started byte and the bytes after it
+    buffer: Vec<u8>,
+    /// Keeps the byte strings handed out, so they live as long as the reader
+    arena: &'a Arena,
+    tail: Option<&'a Chunk>,
+    error: Option<io::Error>,
+}
+
+/// Byte strings read from a stream. Chunks are only appended and never changed while the arena is borrowed,
+/// so the slices handed out stay valid while the reader reuses its buffer
+#[derive(Default)]
+pub(crate) struct Arena {
+    head: OnceCell<Box<Chunk>>,
+}
+
+struct Chunk {
+    bytes: Vec<u8>,
+    next: OnceCell<Box<Chunk>>,
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        // One chunk at a time, the recursive drop of a long list overflows the stack
+        let mut next = self.head.take();
+        while let Some(mut chunk) = next {
+            next = chunk.next.take();
+        }
+    }
+}
+
+impl<'a> Stream<'a> {
+    /// Read `missing` bytes into the buffer. Fails with the number of bytes still missing when the
+    /// stream ends or fails first, the io error is kept for the caller
+    fn read(&mut self, mut missing: usize) -> Result<(), usize> {
+        while missing > 0 {
+            let len = self.buffer.len();
+            // Grow with the bytes that arrived, not by a length read from the input
+            let step = missing.min(len.max(STREAM_CHUNK_SIZE));
+            self.buffer.resize(len + step, 0);
+            let read = match self.source.read(&mut self.buffer[len..]) {
+                Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => Ok(read),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => Ok(0),
+                Err(e) => Err(e),
+            };
+            match read {
+                Ok(read) => {
+                    self.buffer.truncate(len + read);
+                    missing -= read;
+                }
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    self.error = Some(e);
+                    return Err(missing);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy buffered bytes into the arena
+    fn keep(&mut self, start: usize, len: usize) -> &'a [u8] {
+        let arena = self.arena;
+        let cell = match self.tail {
+            Some(tail) => &tail.next,
+            None => &arena.head,
+        };
+        let bytes = self.buffer[start..start + len].to_vec();
+        let chunk: &'a Chunk = cell.get_or_init(|| Box::new(Chunk { bytes, next: OnceCell::new() }));
+        self.tail = Some(chunk);
+        &chunk.bytes
+    }
+}
+
+/// Largest read from a stream at once while its buffer is small
+const STREAM_CHUNK_SIZE: usize = 8 * 1024;
+
+impl<'a> BitReader<'a> {
+    /// Reader starting at the first byte of `bytes`
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_end(bytes, End::Input)
+    }
+
+    /// Reader over `bytes`, with the given meaning of their end
+    pub(crate) fn with_end(bytes: &'a [u8], end: End) -> Self {
+        Self { input: Input::Slice(bytes), pos: 0, bits: 0, base: 0, end }
+    }
+
+    /// Reader that reads the bytes it needs from `source`, and nothing more. Byte strings are kept in `arena`
+    pub(crate) fn from_stream(source: &'a mut dyn Read, arena: &'a Arena) -> Self {
+        let stream = Stream { source, buffer: Vec::new(), arena, tail: None, error: None };
+        Self { input: Input::Stream(stream), pos: 0, bits: 0, base: 0, end: End::Stream }
+    }
+
+    /// The io error that ended the stream, if reading failed because of it
+    pub(crate) fn take_io_error(&mut self) -> Option<io::Error> {
+        match &mut self.input {
+            Input::Slice(_) => None,
+            Input::Stream(stream) => stream.error.take(),
+        }
+    }
+
+    /// Bytes available without reading from the stream, starting at `base`
+    pub(crate) fn buffered(&self) -> &[u8] {
+        match &self.input {
+            Input::Slice(bytes) => bytes,
+            Input::Stream(stream) => &stream.buffer,
+        }
+    }
+
+    /// Make sure the next `len` bytes after the started ones are buffered, reading them from a stream.
+    /// Fails with `NotEnoughBytes` when the input ends before
+    pub(crate) fn fill(&mut self, len: usize) -> Result<(), DeserializationError> {
+        let missing = len.saturating_sub(self.remaining_len());
+        if missing == 0 {
+            return Ok(());
+        }
+        let Input::Stream(stream) = &mut self.input else {
+            return Err(DeserializationErrorKind::NotEnoughBytes(Some(missing)).into());
+        };
+
+        // A bit field may continue in the last started byte, the bytes before it are done
+        let done = self.pos.saturating_sub(1);
+        stream.buffer.drain(..done);
+        self.base += done;
+        self.pos -= done;
+        stream.read(missing).map_err(|missing| DeserializationErrorKind::NotEnoughBytes(Some(missing)).into())
+    }
+
+    /// Position of the next byte in the buffer, see `buffered`
+    pub(crate) fn buffer_pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Start the next byte, after `fill` made sure it's there
+    pub(crate) fn start_byte(&mut self) {
+        self.pos += 1;
+    }
+
+    /// Number of bytes read, including a partially read last byte
+    pub fn position(&self) -> usize {
+        self.base + self.pos
+    }
+
+    /// Number of bits read of the last byte, 0 at a byte boundary
+    pub fn bit_offset(&self) -> u8 {
+        self.bits
+    }
+
+    /// Number of bytes that are not started yet. In a stream only the bytes already read are counted
+    pub fn remaining_len(&self) -> usize {
+        self.buffered().len().saturating_sub(self.pos)
+    }
+
+    /// Whether bytes are left, for values that read until the end of the input (like an `Option` without
+    /// `toggled_by`). Fails with `NotEnoughBytes` when more bytes may follow (`try_from_bytes`) and none are
+    /// left, and with `NotSelfDelimiting` in a stream, where the end of a value isn't the end of the input
+    pub fn has_remaining(&self) -> Result<bool, DeserializationError> {
+        match self.end {
+            End::Input => Ok(self.remaining_len() > 0),
+            End::Open if self.remaining_len() > 0 => Ok(true),
+            End::Open => Err(DeserializationErrorKind::NotEnoughBytes(None).into()),
+            End::Stream => Err(DeserializationErrorKind::NotSelfDelimiting.into()),
+        }
+    }
+
+    /// Skip the rest of the current byte, the next bit field starts at a new byte
+    pub fn align(&mut self) {
+        self.bits = 0;
+    }
+
+    /// Read an unsigned value of `bit_count` bits (1 to 128, otherwise `InvalidBitCount`)
+    pub fn read_bits(&mut self, bit_count: u8, config: &SerializationConfig) -> Result<u128, DeserializationError> {
+        serializers::read_bits_unsigned(self, bit_count, config)
+    }
+
+    /// Read a zigzag encoded signed value of `bit_count` bits (1 to 128, otherwise `InvalidBitCount`)
+    pub fn read_signed_bits(&mut self, bit_count: u8, config: &SerializationConfig) -> Result<i128, DeserializationError> {
+        serializers::read_bits_signed(self, bit_count, config)
+    }
+
+    /// Read a single bit bool
+    pub fn read_bool(&mut self, config: &SerializationConfig) -> Result<bool, DeserializationError> {
+        serializers::read_bool(self, config)
+    }
+
+    /// Read a fixed width integer in the configured byte order, starting at a new byte
+    pub fn read_fixed<T: FixedInt<S>, const S: usize>(&mut self, config: &SerializationConfig) -> Result<T, DeserializationError> {
+        T::read(self, config)
+    }
+
+    /// Read a zigzag encoded fixed width integer, starting at a new byte
+    pub fn read_zigzag<T, const S: usize>(&mut self, config: &SerializationConfig) -> Result<T, DeserializationError>
+    where
+        T: ZigZag,
+        T::Unsigned: FixedInt<S>,
+    {
+        encodings::read_zigzag(self, config)
+    }
+
+    /// Read a dynamic integer (varint), starting at a new byte
+    pub fn read_dyn_int(&mut self) -> Result<u128, DeserializationError> {
+        loop {
+            let rest = self.buffered().get(self.pos..).unwrap_or_default();
+            match dyn_int::read_from_slice(rest) {
+                Ok((val, read)) => {
+                    self.pos += read;
+                    self.bits = 0;
+                    return Ok(val);
+                }
+                // The size is only known at the last byte, a stream reads one more at a time
+                Err(e) if matches!(e.kind, DeserializationErrorKind::NotEnoughBytes(_))
+                    && matches!(self.input, Input::Stream(_)) =>
+                {
+                    self.fill(self.remaining_len() + 1)?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Read `len` bytes, starting at a new byte
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DeserializationError> {
+        self.bits = 0;
+        self.fill(len)?;
+        let start = self.pos;
+        self.pos += len;
+        match &mut self.input {
+            Input::Slice(bytes) => {
+                let bytes: &'a [u8] = bytes;
+                Ok(&bytes[start..start + len])
+            }
+            Input::Stream(stream) => Ok(stream.keep(start, len)),
+        }
+    }
+
+    /// Read all bytes left, starting at a new byte. Fails like `has_remaining` where the end isn't known
+    pub fn read_remaining(&mut self) -> Result<&'a [u8], DeserializationError> {
+        match self.end {
+            End::Input => self.read_bytes(self.remaining_len()),
+            End::Open => Err(DeserializationErrorKind::NotEnoughBytes(None).into()),
+            End::Stream => Err(DeserializationErrorKind::NotSelfDelimiting.into()),
+        }
+    }
+
+    /// Read a length prefix using the configured length prefix strategy
+    pub fn read_length(&mut self, config: &SerializationConfig) -> Result<usize, DeserializationError> {
+        serializers::read_length(self, config)
+    }
+
+    /// Read a value of `len` bytes, starting at a new byte. `read` gets a reader over just these bytes,
+    /// like `BitWriter::write_delimited`. Offsets in its errors are moved to the position in this reader,
+    /// and running out of bytes becomes `LengthTooShort`: more input can't fix it
+    pub fn read_delimited<T>(
+        &mut self,
+        len: usize,
+        read: impl FnOnce(&mut BitReader<'a>) -> Result<T, DeserializationError>,
+    ) -> Result<T, DeserializationError> {
+        let bytes = self.read_bytes(len)?;
+        let start = self.position() - len;
+        read(&mut BitReader::new(bytes)).map_err(|e| e.in_slice(start, len))
+    }
+
+    /// Read a value with a length prefix (`config.length_prefix`), see `read_delimited` and
+    /// `BitWriter::write_length_prefixed`
+    pub fn read_length_prefixed<T>(
+        &mut self,
+        config: &SerializationConfig,
+        read: impl FnOnce(&mut BitReader<'a>) -> Result<T, DeserializationError>,
+    ) -> Result<T, DeserializationError> {
+        let len = self.read_length(config)?;
+        self.read_delimited(len, read)
+    }
+}
@@ -0,0 +1,114 @@
+//! Frame codec for async streams (feature `tokio-codec`). Use it with `tokio_util::codec::Framed`,
+//! `FramedRead` or `FramedWrite` on any `AsyncRead` / `AsyncWrite`, like a TCP stream.
+
+use std::{borrow::Borrow, marker::PhantomData};
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    bits::End, decode_partial, BitReader, BitWriter, Decoded, DeserializationError, DeserializationErrorKind, FromBytes, FromBytesOwned,
+    SerializationConfig, SerializationError, SerializationErrorKind, StreamError, ToBytes,
+};
+
+/// Default maximum size of a frame, see `BinaryCodec::with_max_frame_length`
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Decodes and encodes frames of `T`. A partial frame makes the decoder wait for more bytes
+/// instead of failing with `NotEnoughBytes`.
+pub struct BinaryCodec<T> {
+    config: SerializationConfig,
+    length_delimited: bool,
+    max_frame_length: usize,
+    scratch: Vec<u8>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> BinaryCodec<T> {
+    /// Codec for self-delimiting types: a frame is just the encoded value. Types with a field that reads
+    /// the remaining bytes fail to decode with `NotSelfDelimiting`, use `length_delimited` for them
+    pub fn new(config: SerializationConfig) -> Self {
+        Self {
+            config,
+            length_delimited: false,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            scratch: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Codec that writes a length prefix (`config.length_prefix`) before every value, limited by `config.max_length`.
+    /// With the prefix any type can be framed, also types with a field that reads the remaining bytes
+    pub fn length_delimited(config: SerializationConfig) -> Self {
+        Self { length_delimited: true, ..Self::new(config) }
+    }
+
+    /// Limit the size of a frame, including the length prefix (default `DEFAULT_MAX_FRAME_LENGTH`).
+    /// A frame that is known to be longer fails with `LengthLimitExceeded` before its bytes are buffered
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+}
+
+impl<T: FromBytesOwned> Decoder for BinaryCodec<T> {
+    type Item = T;
+    type Error = StreamError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, StreamError> {
+        // More frames follow, so a value that reads until the end of the buffer fails with `NotSelfDelimiting`
+        let reader = BitReader::with_end(src, End::Stream);
+        let decoded = if self.length_delimited {
+            decode_partial::<Frame<T>>(reader, &self.config)?.map(|frame| frame.0)
+        } else {
+            decode_partial::<T>(reader, &self.config)?
+        };
+
+        match decoded {
+            Decoded::Complete(value, len) => {
+                src.advance(len);
+                Ok(Some(value))
+            }
+            Decoded::Incomplete { needed } => {
+                // `needed` comes from the input, so only check it. The framed reader grows the buffer as bytes arrive
+                let frame_length = src.len().saturating_add(needed.unwrap_or(1));
+                if frame_length > self.max_frame_length {
+                    let kind = DeserializationErrorKind::LengthLimitExceeded(frame_length, self.max_frame_length);
+                    return Err(DeserializationError::from(kind).into());
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Value with a length prefix
+struct Frame<T>(T);
+
+impl<T: FromBytesOwned> FromBytes<'_> for Frame<T> {
+    fn from_bytes_internal(reader: &mut BitReader, config: &SerializationConfig) -> Result<Self, DeserializationError> {
+        let value = reader.read_length_prefixed(config, |reader| T::from_bytes_internal(reader, config))?;
+        Ok(Frame(value))
+    }
+}
+
+impl<T: ToBytes, I: Borrow<T>> Encoder<I> for BinaryCodec<T> {
+    type Error = StreamError;
+
+    fn encode(&mut self, item: I, dst: &mut BytesMut) -> Result<(), StreamError> {
+        self.scratch.clear();
+        let mut writer = BitWriter::new(&mut self.scratch);
+        let item = item.borrow();
+        if self.length_delimited {
+            writer.write_length_prefixed(&self.config, |writer| item.to_bytes_internal(writer, &self.config))?;
+        } else {
+            item.to_bytes_internal(&mut writer, &self.config)?;
+        }
+        if self.scratch.len() > self.max_frame_length {
+            let kind = SerializationErrorKind::LengthLimitExceeded(self.scratch.len(), self.max_frame_length);
+            return Err(SerializationError::from(kind).into());
+        }
+        dst.extend_from_slice(&self.scratch);
+        Ok(())
+    }
+}
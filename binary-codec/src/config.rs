@@ -0,0 +1,94 @@
+/// Byte order used for fixed width integers and fixed width length prefixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    #[default]
+    BigEndian,
+    LittleEndian,
+}
+
+/// Encoding of the length prefix written for `#[dynamic_len]` Vecs, Strings, maps and objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthPrefix {
+    /// Dynamic integer (see `dyn_int`)
+    #[default]
+    Dynamic,
+    /// Fixed 1 byte length
+    U8,
+    /// Fixed 2 byte length, using the configured byte order
+    U16,
+    /// Fixed 4 byte length, using the configured byte order
+    U32,
+}
+
+/// How bit fields (`#[bits]`, bools, ...) are packed into bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitPacking {
+    /// Fields of up to 8 bits that don't fit in the current byte start in a new byte
+    #[default]
+    Aligned,
+    /// Fields continue in the current byte and split across byte boundaries, no bits are wasted
+    Contiguous,
+}
+
+/// Order in which bit fields fill a byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// Fill bytes from the least significant bit upward
+    #[default]
+    Lsb,
+    /// Fill bytes from the most significant bit downward, like network protocols number their bits
+    Msb,
+}
+
+/// Wire profile used when serializing or deserializing a value.
+/// The same derived type can be encoded under different profiles by passing a different config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerializationConfig {
+    /// Byte order of fixed width integers (u16 and wider)
+    pub byte_order: ByteOrder,
+
+    /// Encoding of length prefixes
+    pub length_prefix: LengthPrefix,
+
+    /// Maximum element/byte count accepted for length prefixed or length determined values
+    pub max_length: usize,
+
+    /// Packing of bit fields
+    pub bit_packing: BitPacking,
+
+    /// Order of bits within a byte
+    pub bit_order: BitOrder,
+}
+
+impl SerializationConfig {
+    pub const fn new() -> Self {
+        Self {
+            byte_order: ByteOrder::BigEndian,
+            length_prefix: LengthPrefix::Dynamic,
+            max_length: usize::MAX,
+            bit_packing: BitPacking::Aligned,
+            bit_order: BitOrder::Lsb,
+        }
+    }
+
+    /// Copy of this config with another byte order
+    pub const fn with_byte_order(self, byte_order: ByteOrder) -> Self {
+        Self { byte_order, ..self }
+    }
+
+    /// Copy of this config with another bit packing
+    pub const fn with_bit_packing(self, bit_packing: BitPacking) -> Self {
+        Self { bit_packing, ..self }
+    }
+
+    /// Copy of this config with another bit order
+    pub const fn with_bit_order(self, bit_order: BitOrder) -> Self {
+        Self { bit_order, ..self }
+    }
+}
+
+impl Default for SerializationConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
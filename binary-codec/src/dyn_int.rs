@@ -1,10 +1,14 @@
-use crate::DeserializationError;
+use crate::{DeserializationError, DeserializationErrorKind};
 
 /// Gives encoded size in bytes
 ///
 /// # Arguments
 /// * `nr` - number to encode
 pub fn encoded_size(nr: u128) -> usize {
+    if nr == 0 {
+        return 1;
+    }
+
     let mut res = 0;
     let mut nr = nr;
     while nr > 0 {
@@ -19,33 +23,52 @@ pub fn encoded_size(nr: u128) -> usize {
 /// # Arguments
 /// * `nr` - number to encode
 pub fn encode(nr: u128) -> Vec<u8> {
-    let mut res = Vec::new();
+    let mut res = [0; MAX_ENCODED_SIZE];
+    let len = encode_into(nr, &mut res);
+    res[..len].to_vec()
+}
+
+/// Encodes a number into the start of a buffer, returns the number of bytes used.
+///
+/// # Arguments
+/// * `nr` - number to encode
+/// * `buf` - buffer of at least `encoded_size(nr)` bytes
+pub fn encode_into(nr: u128, buf: &mut [u8]) -> usize {
+    let mut len = 0;
     let mut nr = nr;
-    while nr > 0 {
+    loop {
         let mut encoded = nr % 128;
         nr /= 128;
         if nr > 0 {
             encoded |= 128;
         }
-        res.push(encoded as u8);
+        buf[len] = encoded as u8;
+        len += 1;
+        if nr == 0 {
+            return len;
+        }
     }
-    res
 }
 
-/// Decodes a number from a slice of bytes.
+/// Decodes a number from a slice of bytes. Bits beyond 128 bits are dropped.
 ///
 /// # Arguments
 /// * `data` - slice of bytes to decode
 pub fn decode(data: &[u8]) -> u128 {
-    let mut num = 0;
-    let mut multiplier = 1;
-    for byte in data {
-        num += (*byte as u128 & 127) * multiplier;
-        multiplier *= 128;
+    let mut num: u128 = 0;
+    for (i, byte) in data.iter().enumerate() {
+        let shift = 7 * i as u32;
+        if shift >= 128 {
+            break;
+        }
+        num |= (*byte as u128 & 127) << shift;
     }
     num
 }
 
+/// Maximum number of bytes of an encoded u128
+pub const MAX_ENCODED_SIZE: usize = 19;
+
 /// Decodes a number from a slice of bytes when size of encoded number is unknown, returning the number and the number of bytes read.
 ///
 /// # Arguments
@@ -56,8 +79,14 @@ pub fn decode(data: &[u8]) -> u128 {
 pub fn read_from_slice(data: &[u8]) -> Result<(u128, usize), DeserializationError> {
     let mut idx = 0;
     loop {
-        if idx > data.len() - 1 {
-            break Err(DeserializationError::NotEnoughBytes(1));
+        // The size is only known at the last byte
+        if idx >= data.len() {
+            break Err(DeserializationErrorKind::NotEnoughBytes(None).into());
+        }
+
+        // The last byte of a u128 only has 2 bits left (18 * 7 = 126)
+        if idx == MAX_ENCODED_SIZE - 1 && data[idx] > 0b11 {
+            break Err(DeserializationErrorKind::DynIntOverflow.into());
         }
 
         if (data[idx] & 1 << 7) == 0 {
@@ -116,6 +145,14 @@ mod test {
         assert!(decoded.is_err());
     }
 
+    #[test]
+    fn can_encode_zero_in_1_byte() {
+        let encoded = encode(0);
+        assert_eq!(vec![0], encoded);
+        assert_eq!(1, encoded_size(0));
+        assert_eq!((0, 1), read_from_slice(&encoded).unwrap());
+    }
+
     #[test]
     fn can_encode_nr_lt_128_in_1_byte() {
         let encoded = encode(127);
@@ -133,4 +170,20 @@ mod test {
         let four_bytes = 268435455;
         assert_eq!(4, encoded_size(four_bytes));
     }
+
+    #[test]
+    fn cant_decode_empty_slice() {
+        assert_eq!(read_from_slice(&[]).unwrap_err().kind, DeserializationErrorKind::NotEnoughBytes(None));
+    }
+
+    #[test]
+    fn can_decode_max_and_reject_overflow() {
+        let encoded = encode(u128::MAX);
+        assert_eq!(19, encoded.len());
+        assert_eq!((u128::MAX, 19), read_from_slice(&encoded).unwrap());
+
+        let too_long = [0xff; 30];
+        assert_eq!(read_from_slice(&too_long).unwrap_err().kind, DeserializationErrorKind::DynIntOverflow);
+        assert_eq!(u128::MAX, decode(&too_long));
+    }
 }
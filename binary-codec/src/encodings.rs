@@ -1,61 +1,84 @@
-use crate::{DeserializationError, SerializationError};
+use crate::{BitReader, BitWriter, ByteOrder, DeserializationError, SerializationConfig, SerializationError};
 
 pub fn write_zigzag<T, const S: usize>(
     val: T,
-    bytes: &mut Vec<u8>,
-    pos: &mut usize,
-    bits: &mut u8,
+    writer: &mut BitWriter,
+    config: &SerializationConfig,
 ) -> Result<(), SerializationError>
 where
     T: ZigZag,
     T::Unsigned: FixedInt<S>,
 {
     let encoded = val.to_unsigned();
-    encoded.write(bytes, pos, bits)
+    encoded.write(writer, config)
 }
 
 pub fn read_zigzag<T, const S: usize>(
-    bytes: &[u8],
-    pos: &mut usize,
-    bits: &mut u8,
+    reader: &mut BitReader,
+    config: &SerializationConfig,
 ) -> Result<T, DeserializationError>
 where
     T: ZigZag,
     T::Unsigned: FixedInt<S>,
 {
-    let raw = T::Unsigned::read(bytes, pos, bits)?;
+    let raw = T::Unsigned::read(reader, config)?;
     Ok(T::to_signed(raw))
 }
 
+pub fn write_float<T, const S: usize>(
+    val: T,
+    writer: &mut BitWriter,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError>
+where
+    T: Float,
+    T::Bits: FixedInt<S>,
+{
+    val.to_raw().write(writer, config)
+}
+
+pub fn read_float<T, const S: usize>(
+    reader: &mut BitReader,
+    config: &SerializationConfig,
+) -> Result<T, DeserializationError>
+where
+    T: Float,
+    T::Bits: FixedInt<S>,
+{
+    let raw = T::Bits::read(reader, config)?;
+    Ok(T::from_raw(raw))
+}
+
 // Fixed int implementations
 pub trait FixedInt<const S: usize> : Sized {    
     fn serialize(self) -> [u8; S];
     fn deserialize(bytes: &[u8]) -> Self;
+    fn serialize_le(self) -> [u8; S];
+    fn deserialize_le(bytes: &[u8]) -> Self;
 
-    fn write(
-        self,
-        bytes: &mut Vec<u8>,
-        pos: &mut usize,
-        bits: &mut u8,
-    ) -> Result<(), SerializationError> {
-        *bits = 0;
-        bytes.extend_from_slice(&self.serialize());
-        *pos += S;
-        Ok(())
-    }
-
-    fn read(
-        bytes: &[u8],
-        pos: &mut usize,
-        bits: &mut u8,
-    ) -> Result<Self, DeserializationError> {
-        *bits = 0;
-        if *pos + S > bytes.len() {
-            return Err(DeserializationError::NotEnoughBytes(*pos + S - bytes.len()));
+    fn write(self, writer: &mut BitWriter, config: &SerializationConfig) -> Result<(), SerializationError> {
+        self.write_with_order(config.byte_order, writer)
+    }
+
+    fn read(reader: &mut BitReader, config: &SerializationConfig) -> Result<Self, DeserializationError> {
+        Self::read_with_order(config.byte_order, reader)
+    }
+
+    /// Write using the given byte order, regardless of the configured byte order
+    fn write_with_order(self, byte_order: ByteOrder, writer: &mut BitWriter) -> Result<(), SerializationError> {
+        match byte_order {
+            ByteOrder::BigEndian => writer.write_bytes(&self.serialize()),
+            ByteOrder::LittleEndian => writer.write_bytes(&self.serialize_le()),
         }
-        let val = Self::deserialize(&bytes[*pos..*pos + S]);
-        *pos += S;
-        Ok(val)
+    }
+
+    /// Read using the given byte order, regardless of the configured byte order
+    fn read_with_order(byte_order: ByteOrder, reader: &mut BitReader) -> Result<Self, DeserializationError> {
+        let bytes = reader.read_bytes(S)?;
+        Ok(match byte_order {
+            ByteOrder::BigEndian => Self::deserialize(bytes),
+            ByteOrder::LittleEndian => Self::deserialize_le(bytes),
+        })
     }
 }
 
@@ -65,7 +88,15 @@ impl FixedInt<1> for u8 {
     }
 
     fn deserialize(bytes: &[u8]) -> Self {
-        u8::from_be_bytes(bytes.try_into().unwrap()).try_into().unwrap()
+        u8::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn serialize_le(self) -> [u8; 1] {
+        self.to_le_bytes()
+    }
+
+    fn deserialize_le(bytes: &[u8]) -> Self {
+        u8::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
@@ -75,7 +106,15 @@ impl FixedInt<2> for u16 {
     }
 
     fn deserialize(bytes: &[u8]) -> Self {
-        u16::from_be_bytes(bytes.try_into().unwrap()).try_into().unwrap()
+        u16::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn serialize_le(self) -> [u8; 2] {
+        self.to_le_bytes()
+    }
+
+    fn deserialize_le(bytes: &[u8]) -> Self {
+        u16::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
@@ -85,7 +124,15 @@ impl FixedInt<4> for u32 {
     }
 
     fn deserialize(bytes: &[u8]) -> Self {
-        u32::from_be_bytes(bytes.try_into().unwrap()).try_into().unwrap()
+        u32::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn serialize_le(self) -> [u8; 4] {
+        self.to_le_bytes()
+    }
+
+    fn deserialize_le(bytes: &[u8]) -> Self {
+        u32::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
@@ -95,7 +142,15 @@ impl FixedInt<8> for u64 {
     }
 
     fn deserialize(bytes: &[u8]) -> Self {
-        u64::from_be_bytes(bytes.try_into().unwrap()).try_into().unwrap()
+        u64::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn serialize_le(self) -> [u8; 8] {
+        self.to_le_bytes()
+    }
+
+    fn deserialize_le(bytes: &[u8]) -> Self {
+        u64::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
@@ -105,7 +160,15 @@ impl FixedInt<16> for u128 {
     }
 
     fn deserialize(bytes: &[u8]) -> Self {
-        u128::from_be_bytes(bytes.try_into().unwrap()).try_into().unwrap()
+        u128::from_be_bytes(bytes.try_into().unwrap())
+    }
+
+    fn serialize_le(self) -> [u8; 16] {
+        self.to_le_bytes()
+    }
+
+    fn deserialize_le(bytes: &[u8]) -> Self {
+        u128::from_le_bytes(bytes.try_into().unwrap())
     }
 }
 
@@ -167,6 +230,93 @@ impl ZigZag for i128 {
     }
 }
 
+// IEEE-754 float implementations, written as fixed int of the same size
+pub trait Float {
+    type Bits;
+
+    fn to_raw(self) -> Self::Bits;
+    fn from_raw(bits: Self::Bits) -> Self;
+}
+
+impl Float for f32 {
+    type Bits = u32;
+    fn to_raw(self) -> u32 {
+        self.to_bits()
+    }
+    fn from_raw(bits: u32) -> f32 {
+        f32::from_bits(bits)
+    }
+}
+
+impl Float for f64 {
+    type Bits = u64;
+    fn to_raw(self) -> u64 {
+        self.to_bits()
+    }
+    fn from_raw(bits: u64) -> f64 {
+        f64::from_bits(bits)
+    }
+}
+
+/// Convert to IEEE-754 half precision bits, rounding to nearest even.
+/// Values too large for a half float become infinity.
+pub fn f32_to_f16_bits(val: f32) -> u16 {
+    let x = val.to_bits();
+    let sign = ((x >> 16) & 0x8000) as u16;
+    let exp = ((x >> 23) & 0xff) as i32;
+    let man = x & 0x7f_ffff;
+
+    // Infinity or NaN (keep NaN a NaN)
+    if exp == 0xff {
+        let nan = if man != 0 { 0x200 | (man >> 13) as u16 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let half_exp = exp - 127 + 15;
+    if half_exp >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    // Subnormal half float (or zero)
+    if half_exp <= 0 {
+        if half_exp < -10 {
+            return sign;
+        }
+        let man = man | 0x80_0000;
+        let shift = (14 - half_exp) as u32;
+        let half_man = man >> shift;
+        let rem = man & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round_up = rem > halfway || (rem == halfway && half_man & 1 == 1);
+        return sign | (half_man + round_up as u32) as u16;
+    }
+
+    // Rounding may carry into the exponent, which is still correct (up to infinity)
+    let half = ((half_exp as u32) << 10) | (man >> 13);
+    let rem = man & 0x1fff;
+    let round_up = rem > 0x1000 || (rem == 0x1000 && half & 1 == 1);
+    sign | (half + round_up as u32) as u16
+}
+
+/// Convert IEEE-754 half precision bits to f32 (lossless)
+pub fn f16_bits_to_f32(bits: u16) -> f32 {
+    let sign = ((bits & 0x8000) as u32) << 16;
+    let exp = ((bits >> 10) & 0x1f) as u32;
+    let man = (bits & 0x3ff) as u32;
+
+    let x = match exp {
+        0 if man == 0 => sign,
+        0 => {
+            let val = man as f32 * f32::powi(2.0, -24);
+            return if sign != 0 { -val } else { val };
+        }
+        0x1f => sign | 0x7f80_0000 | (man << 13),
+        _ => sign | ((exp + 127 - 15) << 23) | (man << 13),
+    };
+
+    f32::from_bits(x)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,52 +363,101 @@ mod tests {
     #[test]
     fn test_write_read_zigzag_i32() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let config = SerializationConfig::default();
         let val: i32 = -123;
-        write_zigzag::<i32, 4>(val, &mut bytes, &mut pos, &mut bits).unwrap();
-        pos = 0;
-        bits = 0;
-        let decoded = read_zigzag::<i32, 4>(&bytes, &mut pos, &mut bits).unwrap();
+        write_zigzag::<i32, 4>(val, &mut BitWriter::new(&mut bytes), &config).unwrap();
+        let decoded = read_zigzag::<i32, 4>(&mut BitReader::new(&bytes), &config).unwrap();
         assert_eq!(decoded, val);
     }
 
     #[test]
     fn test_write_read_zigzag_i64() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let config = SerializationConfig::default();
         let val: i64 = 456789;
-        write_zigzag::<i64, 8>(val, &mut bytes, &mut pos, &mut bits).unwrap();
-        pos = 0;
-        bits = 0;
-        let decoded = read_zigzag::<i64, 8>(&bytes, &mut pos, &mut bits).unwrap();
+        write_zigzag::<i64, 8>(val, &mut BitWriter::new(&mut bytes), &config).unwrap();
+        let decoded = read_zigzag::<i64, 8>(&mut BitReader::new(&bytes), &config).unwrap();
         assert_eq!(decoded, val);
     }
 
     #[test]
     fn test_write_read_fixedint_u32() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let config = SerializationConfig::default();
         let val: u32 = 0b1010_1010_1010_1010_1010_1010_1010_1010;
-        val.write(&mut bytes, &mut pos, &mut bits).unwrap();
-        pos = 0;
-        bits = 0;
-        let decoded = u32::read(&bytes, &mut pos, &mut bits).unwrap();
+        val.write(&mut BitWriter::new(&mut bytes), &config).unwrap();
+        let decoded = u32::read(&mut BitReader::new(&bytes), &config).unwrap();
         assert_eq!(decoded, val);
     }
 
     #[test]
     fn test_write_read_fixedint_u128() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let config = SerializationConfig::default();
         let val: u128 = 0b1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010_1010;
-        val.write(&mut bytes, &mut pos, &mut bits).unwrap();
-        pos = 0;
-        bits = 0;
-        let decoded = u128::read(&bytes, &mut pos, &mut bits).unwrap();
+        val.write(&mut BitWriter::new(&mut bytes), &config).unwrap();
+        let decoded = u128::read(&mut BitReader::new(&bytes), &config).unwrap();
+        assert_eq!(decoded, val);
+    }
+
+    #[test]
+    fn test_write_read_fixedint_u32_little_endian() {
+        let mut bytes = Vec::new();
+        let config = SerializationConfig { byte_order: ByteOrder::LittleEndian, ..Default::default() };
+        let val: u32 = 0x0102_0304;
+        val.write(&mut BitWriter::new(&mut bytes), &config).unwrap();
+        assert_eq!(bytes, vec![4, 3, 2, 1]);
+        let decoded = u32::read(&mut BitReader::new(&bytes), &config).unwrap();
         assert_eq!(decoded, val);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_write_read_fixedint_mixed_order() {
+        let mut bytes = Vec::new();
+        let mut writer = BitWriter::new(&mut bytes);
+        0x0102u16.write_with_order(ByteOrder::LittleEndian, &mut writer).unwrap();
+        0x0102u16.write_with_order(ByteOrder::BigEndian, &mut writer).unwrap();
+        assert_eq!(bytes, vec![2, 1, 1, 2]);
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(u16::read_with_order(ByteOrder::LittleEndian, &mut reader).unwrap(), 0x0102);
+        assert_eq!(u16::read_with_order(ByteOrder::BigEndian, &mut reader).unwrap(), 0x0102);
+    }
+
+    #[test]
+    fn test_write_read_float() {
+        let mut bytes = Vec::new();
+        let config = SerializationConfig::default().with_byte_order(ByteOrder::LittleEndian);
+        let mut writer = BitWriter::new(&mut bytes);
+        write_float(1.5f32, &mut writer, &config).unwrap();
+        write_float(-2.25f64, &mut writer, &config).unwrap();
+        assert_eq!(bytes[..4], 1.5f32.to_le_bytes());
+        assert_eq!(bytes[4..], (-2.25f64).to_le_bytes());
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(read_float::<f32, 4>(&mut reader, &config).unwrap(), 1.5);
+        assert_eq!(read_float::<f64, 8>(&mut reader, &config).unwrap(), -2.25);
+    }
+
+    #[test]
+    fn test_f16_conversion() {
+        let cases: [(f32, u16); 8] = [
+            (0.0, 0x0000),
+            (-0.0, 0x8000),
+            (1.0, 0x3c00),
+            (-2.5, 0xc100),
+            (65504.0, 0x7bff),
+            (1e6, 0x7c00),
+            (f32::INFINITY, 0x7c00),
+            (5.960_464_5e-8, 0x0001),
+        ];
+        for (val, half) in cases {
+            assert_eq!(f32_to_f16_bits(val), half, "f16 encoding failed for {}", val);
+            if val.abs() < 1e5 {
+                assert_eq!(f16_bits_to_f32(half), val, "f16 decoding failed for {:#x}", half);
+            }
+        }
+        assert!(f16_bits_to_f32(f32_to_f16_bits(f32::NAN)).is_nan());
+        // 1 + 2^-11 is exactly halfway between 1 and the next half float, rounds to even
+        assert_eq!(f32_to_f16_bits(1.0 + f32::powi(2.0, -11)), 0x3c00);
+        assert_eq!(f32_to_f16_bits(1.0 + 3.0 * f32::powi(2.0, -11)), 0x3c02);
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt;
+
+/// Reason a value could not be serialized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerializationErrorKind {
+    /// Signed value is out of bounds (value, min, max)
+    ValueOutOfBounds(i128, i128, i128),
+
+    /// Unsigned value is out of bounds (value, max), the minimum is 0
+    UnsignedOutOfBounds(u128, u128),
+
+    /// Unexpected size (expected, actual)
+    UnexpectedLength(usize, usize),
+
+    /// Length exceeds the configured maximum (length, max)
+    LengthLimitExceeded(usize, usize),
+
+    /// The value doesn't fit in the buffer passed to `to_slice` (buffer size)
+    BufferTooSmall(usize),
+
+    /// Bit count is not in 1..=128
+    InvalidBitCount(u8),
+
+    /// A `toggled_by` field doesn't match its `Option` (value of the field): true with `None`, or false with `Some`
+    ToggleMismatch(bool),
+
+    /// An indexed field reference like `lens.2` points past the end of the field (index, length)
+    ReferenceOutOfBounds(usize, usize),
+}
+
+impl fmt::Display for SerializationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ValueOutOfBounds(value, min, max) => {
+                write!(f, "value {} is out of bounds ({}..={})", value, min, max)
+            }
+            Self::UnsignedOutOfBounds(value, max) => write!(f, "value {} is out of bounds (0..={})", value, max),
+            Self::UnexpectedLength(expected, actual) => {
+                write!(f, "expected length {}, found {}", expected, actual)
+            }
+            Self::LengthLimitExceeded(len, max) => write!(f, "length {} exceeds the maximum of {}", len, max),
+            Self::BufferTooSmall(size) => write!(f, "buffer of {} bytes is too small for the value", size),
+            Self::InvalidBitCount(count) => write!(f, "bit count {} is not in 1..=128", count),
+            Self::ToggleMismatch(true) => write!(f, "toggled_by field is true, but the value is None"),
+            Self::ToggleMismatch(false) => write!(f, "toggled_by field is false, but the value is Some"),
+            Self::ReferenceOutOfBounds(index, len) => {
+                write!(f, "referenced index {} is out of bounds for {} elements", index, len)
+            }
+        }
+    }
+}
+
+/// Serialization error, with the field it happened in.
+/// The context is filled in by the derived code while the error bubbles up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerializationError {
+    /// Why serialization failed
+    pub kind: SerializationErrorKind,
+
+    /// Outermost derived type the error passed through
+    pub type_name: Option<&'static str>,
+
+    /// Path from the outermost type to the value that failed, e.g. `payload[3].header.kind`
+    pub path: Vec<PathSegment>,
+}
+
+impl SerializationError {
+    pub fn new(kind: SerializationErrorKind) -> Self {
+        Self { kind, type_name: None, path: Vec::new() }
+    }
+
+    /// Add the field the error happened in
+    pub fn in_field(mut self, name: &'static str) -> Self {
+        self.path.insert(0, PathSegment::Field(name));
+        self
+    }
+
+    /// Add the element of a Vec, array or map the error happened in
+    pub fn in_element(mut self, index: usize) -> Self {
+        self.path.insert(0, PathSegment::Index(index));
+        self
+    }
+
+    /// Add the enum variant the error happened in
+    pub fn in_variant(mut self, name: &'static str) -> Self {
+        self.path.insert(0, PathSegment::Variant(name));
+        self
+    }
+
+    /// Set the type the error passed through, the outermost type is kept
+    pub fn in_type(mut self, name: &'static str) -> Self {
+        self.type_name = Some(name);
+        self
+    }
+}
+
+impl From<SerializationErrorKind> for SerializationError {
+    fn from(kind: SerializationErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl fmt::Display for SerializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        fmt_location(f, self.type_name, &self.path)
+    }
+}
+
+impl std::error::Error for SerializationError {}
+
+/// Reason a value could not be deserialized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeserializationErrorKind {
+    /// The input ends before the value. Holds the number of bytes missing for the value being read
+    /// (later values may need more), or `None` when it isn't known yet, like in a dynamic integer
+    NotEnoughBytes(Option<usize>),
+
+    /// Unknown enum discriminator
+    UnknownDiscriminant(u64),
+
+    /// Length exceeds the configured maximum (length, max)
+    LengthLimitExceeded(usize, usize),
+
+    /// Reserved bits don't have the expected value (expected, found)
+    InvalidReservedValue(u128, u128),
+
+    /// String is not valid UTF-8
+    InvalidUtf8,
+
+    /// Dynamic integer is longer than the largest supported integer (u128)
+    DynIntOverflow,
+
+    /// Unexpected number of elements (expected, actual)
+    UnexpectedLength(usize, usize),
+
+    /// Magic bytes or constant field don't have the expected value.
+    /// Constants are shown as big endian bytes of the field type
+    InvalidMagic { expected: Vec<u8>, found: Vec<u8> },
+
+    /// Length delimited value needs more bytes than its length
+    LengthTooShort(usize),
+
+    /// Bit count is not in 1..=128
+    InvalidBitCount(u8),
+
+    /// An indexed field reference like `lens.2` points past the end of the decoded field (index, length)
+    ReferenceOutOfBounds(usize, usize),
+
+    /// The value reads until the end of the input (like a `Vec` without length), but is read from a stream
+    /// where more values follow. Give it a length, or read it from a length delimited frame
+    NotSelfDelimiting,
+}
+
+impl fmt::Display for DeserializationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEnoughBytes(Some(missing)) => write!(f, "not enough bytes ({} missing)", missing),
+            Self::NotEnoughBytes(None) => write!(f, "not enough bytes"),
+            Self::UnknownDiscriminant(disc) => write!(f, "unknown discriminant {}", disc),
+            Self::LengthLimitExceeded(len, max) => write!(f, "length {} exceeds the maximum of {}", len, max),
+            Self::InvalidReservedValue(expected, found) => {
+                write!(f, "reserved bits should be {:#x}, found {:#x}", expected, found)
+            }
+            Self::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            Self::DynIntOverflow => write!(f, "dynamic integer overflows u128"),
+            Self::UnexpectedLength(expected, actual) => {
+                write!(f, "expected {} elements, found {}", expected, actual)
+            }
+            Self::InvalidMagic { expected, found } => {
+                write!(f, "expected magic {:02x?}, found {:02x?}", expected, found)
+            }
+            Self::LengthTooShort(len) => write!(f, "length {} is too short for the value", len),
+            Self::InvalidBitCount(count) => write!(f, "bit count {} is not in 1..=128", count),
+            Self::ReferenceOutOfBounds(index, len) => {
+                write!(f, "referenced index {} is out of bounds for {} elements", index, len)
+            }
+            Self::NotSelfDelimiting => write!(f, "value reads until the end of the input, which a stream doesn't have"),
+        }
+    }
+}
+
+/// Step in the path to the value that failed to deserialize
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Struct or variant field (name, or position for tuple fields)
+    Field(&'static str),
+    /// Element of a Vec, array or map
+    Index(usize),
+    /// Enum variant
+    Variant(&'static str),
+}
+
+/// Deserialization error, with the location where it happened.
+/// The context is filled in by the derived code while the error bubbles up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializationError {
+    /// Why deserialization failed
+    pub kind: DeserializationErrorKind,
+
+    /// Outermost derived type the error passed through
+    pub type_name: Option<&'static str>,
+
+    /// Path from the outermost type to the value that failed, e.g. `payload[3].header.kind`
+    pub path: Vec<PathSegment>,
+
+    /// Byte and bit offset where the innermost failing value starts
+    pub offset: Option<(usize, u8)>,
+}
+
+impl DeserializationError {
+    pub fn new(kind: DeserializationErrorKind) -> Self {
+        Self { kind, type_name: None, path: Vec::new(), offset: None }
+    }
+
+    /// Add the field the error happened in. `pos` and `bits` are the cursor at the start of the field
+    pub fn in_field(mut self, name: &'static str, pos: usize, bits: u8) -> Self {
+        self.path.insert(0, PathSegment::Field(name));
+        self.set_offset(pos, bits)
+    }
+
+    /// Add the element of a Vec, array or map the error happened in
+    pub fn in_element(mut self, index: usize, pos: usize, bits: u8) -> Self {
+        self.path.insert(0, PathSegment::Index(index));
+        self.set_offset(pos, bits)
+    }
+
+    /// Add the enum variant the error happened in
+    pub fn in_variant(mut self, name: &'static str) -> Self {
+        self.path.insert(0, PathSegment::Variant(name));
+        self
+    }
+
+    /// Set the type the error passed through, the outermost type is kept
+    pub fn in_type(mut self, name: &'static str) -> Self {
+        self.type_name = Some(name);
+        self
+    }
+
+    /// Add the sub slice of `len` bytes at `start` the error happened in (like length delimited objects).
+    /// The offset is moved by `start`, and running out of bytes in the slice means its length is too short,
+    /// not that the input is incomplete
+    pub fn in_slice(mut self, start: usize, len: usize) -> Self {
+        if let Some((byte, _)) = self.offset.as_mut() {
+            *byte += start;
+        }
+        if let DeserializationErrorKind::NotEnoughBytes(_) = self.kind {
+            self.kind = DeserializationErrorKind::LengthTooShort(len);
+        }
+        self
+    }
+
+    /// Set the offset the error happened at, only the innermost offset is kept
+    pub fn set_offset(mut self, pos: usize, bits: u8) -> Self {
+        if self.offset.is_none() {
+            // pos is the number of bytes started, bits the bits used of the last one
+            self.offset = Some(if bits == 0 { (pos, 0) } else { (pos - 1, bits) });
+        }
+        self
+    }
+}
+
+impl From<DeserializationErrorKind> for DeserializationError {
+    fn from(kind: DeserializationErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl fmt::Display for DeserializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        fmt_location(f, self.type_name, &self.path)?;
+
+        if let Some((byte, bit)) = self.offset {
+            write!(f, " at byte {}, bit {}", byte, bit)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for DeserializationError {}
+
+/// Write ` in Type.field[3]::Variant`, or nothing if there is no context
+fn fmt_location(f: &mut fmt::Formatter<'_>, type_name: Option<&str>, path: &[PathSegment]) -> fmt::Result {
+    if type_name.is_none() && path.is_empty() {
+        return Ok(());
+    }
+
+    write!(f, " in ")?;
+    if let Some(type_name) = type_name {
+        write!(f, "{}", type_name)?;
+    }
+
+    for (i, segment) in path.iter().enumerate() {
+        match segment {
+            PathSegment::Field(name) if i == 0 && type_name.is_none() => write!(f, "{}", name)?,
+            PathSegment::Field(name) => write!(f, ".{}", name)?,
+            PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            PathSegment::Variant(name) => write!(f, "::{}", name)?,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Types and helpers shared by the fuzz test in `tests/fuzz.rs` and the cargo-fuzz targets in `fuzz/`.
+//! Not part of the public API, enabled with the `fuzzing` feature.
+
+use std::collections::HashMap;
+
+use crate::{
+    BitOrder, BitPacking, ByteOrder, FromBytes, FromBytesOwned, LengthPrefix, SerializationConfig, ToBytes,
+};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+pub struct Inner {
+    #[bits = 5]
+    pub a: u8,
+    #[bits = 13]
+    pub b: i16,
+    #[dynamic]
+    pub c: u64,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[disc_dynamic]
+pub enum Kind {
+    A,
+    B(#[dynamic_len] String),
+    #[disc = 9]
+    C { x: f32, #[f16] y: f32 },
+    #[fallback]
+    Unknown(u8),
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+// The string form, a byte string attribute crashes rustc when read from the metadata of this crate
+#[magic = "FZ"]
+pub struct Everything {
+    #[reserved(bits = 2, value = 1)]
+    pub reserved: (),
+    pub flag: bool,
+    pub count: u8,
+    #[length_determined_by = "count"]
+    pub values: Vec<u16>,
+    #[toggled_by = "flag"]
+    pub maybe: Option<i32>,
+    #[dynamic_len]
+    pub name: String,
+    #[dynamic_len]
+    pub inner: Inner,
+    pub kinds: [Kind; 2],
+    #[dynamic_len(2)]
+    pub map: HashMap<u8, Vec<bool>>,
+    #[fixed_point(scale = 10, bits = 11)]
+    pub temperature: f64,
+    #[constant = 0x7e]
+    pub end: u8,
+    #[dynamic_len(2)]
+    pub nested: Vec<Vec<u8>>,
+    #[dynamic_len]
+    pub lens: Vec<u8>,
+    #[length_determined_by = "lens.1"]
+    pub sized: Vec<u8>,
+    #[dynamic_len]
+    pub flags: Vec<bool>,
+    #[toggled_by = "flags.2"]
+    pub indexed: Option<u8>,
+    pub rest: String,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[bit_order = "msb"]
+#[bit_packing = "contiguous"]
+pub struct Packed {
+    #[bits = 3]
+    pub a: u8,
+    #[bits = 30]
+    pub b: u32,
+    #[pad_bits = 5]
+    pub c: bool,
+    #[align = 4]
+    pub d: u16,
+    pub tail: Vec<Inner>,
+}
+
+/// Config selected by the bits of a fuzzer input byte
+pub fn config(selector: u8) -> SerializationConfig {
+    SerializationConfig {
+        byte_order: if selector & 1 == 0 { ByteOrder::BigEndian } else { ByteOrder::LittleEndian },
+        length_prefix: match (selector >> 1) & 3 {
+            0 => LengthPrefix::Dynamic,
+            1 => LengthPrefix::U8,
+            2 => LengthPrefix::U16,
+            _ => LengthPrefix::U32,
+        },
+        max_length: if selector & 8 == 0 { usize::MAX } else { 16 },
+        bit_packing: if selector & 16 == 0 { BitPacking::Aligned } else { BitPacking::Contiguous },
+        bit_order: if selector & 32 == 0 { BitOrder::Lsb } else { BitOrder::Msb },
+    }
+}
+
+/// Decode the bytes, and encode the result again if that succeeded
+pub fn check<T: FromBytesOwned + ToBytes>(bytes: &[u8], config: &SerializationConfig) {
+    if let Ok(value) = T::from_bytes(bytes, config) {
+        let _ = value.to_bytes(config);
+    }
+}
@@ -0,0 +1,128 @@
+//! Trait implementations for primitive types, encoded the same way as a field of that type
+//! without attributes. This allows primitives to be used as generic parameters of derived types.
+
+use crate::{
+    encodings::{self, FixedInt},
+    serializers, BitReader, BitWriter, DeserializationError, FromBytes, SerializationConfig, SerializationError, ToBytes,
+};
+
+impl ToBytes for bool {
+    fn to_bytes_internal(
+        &self,
+        writer: &mut BitWriter,
+        config: &SerializationConfig,
+    ) -> Result<(), SerializationError> {
+        serializers::write_bool(*self, writer, config)
+    }
+}
+
+impl FromBytes<'_> for bool {
+    fn from_bytes_internal(
+        reader: &mut BitReader,
+        config: &SerializationConfig,
+    ) -> Result<Self, DeserializationError> {
+        serializers::read_bool(reader, config)
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl ToBytes for $ty {
+                fn to_bytes_internal(
+                    &self,
+                    writer: &mut BitWriter,
+                    config: &SerializationConfig,
+                ) -> Result<(), SerializationError> {
+                    FixedInt::write(*self, writer, config)
+                }
+            }
+
+            impl FromBytes<'_> for $ty {
+                fn from_bytes_internal(
+                    reader: &mut BitReader,
+                    config: &SerializationConfig,
+                ) -> Result<Self, DeserializationError> {
+                    FixedInt::read(reader, config)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed {
+    ($($ty:ty),*) => {
+        $(
+            impl ToBytes for $ty {
+                fn to_bytes_internal(
+                    &self,
+                    writer: &mut BitWriter,
+                    config: &SerializationConfig,
+                ) -> Result<(), SerializationError> {
+                    encodings::write_zigzag(*self, writer, config)
+                }
+            }
+
+            impl FromBytes<'_> for $ty {
+                fn from_bytes_internal(
+                    reader: &mut BitReader,
+                    config: &SerializationConfig,
+                ) -> Result<Self, DeserializationError> {
+                    encodings::read_zigzag(reader, config)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float {
+    ($($ty:ty),*) => {
+        $(
+            impl ToBytes for $ty {
+                fn to_bytes_internal(
+                    &self,
+                    writer: &mut BitWriter,
+                    config: &SerializationConfig,
+                ) -> Result<(), SerializationError> {
+                    encodings::write_float(*self, writer, config)
+                }
+            }
+
+            impl FromBytes<'_> for $ty {
+                fn from_bytes_internal(
+                    reader: &mut BitReader,
+                    config: &SerializationConfig,
+                ) -> Result<Self, DeserializationError> {
+                    encodings::read_float(reader, config)
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128);
+impl_signed!(i8, i16, i32, i64, i128);
+impl_float!(f32, f64);
+
+/// A `bytes::Bytes` field is encoded like a `Vec<u8>` field: the remaining bytes, or a length prefix with `#[dynamic_len]`
+#[cfg(feature = "bytes")]
+impl ToBytes for bytes::Bytes {
+    fn to_bytes_internal(
+        &self,
+        writer: &mut BitWriter,
+        config: &SerializationConfig,
+    ) -> Result<(), SerializationError> {
+        serializers::write_byte_slice(self, writer, config)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl FromBytes<'_> for bytes::Bytes {
+    fn from_bytes_internal(
+        reader: &mut BitReader,
+        config: &SerializationConfig,
+    ) -> Result<Self, DeserializationError> {
+        let slice = serializers::read_remaining(reader, config)?;
+        Ok(bytes::Bytes::copy_from_slice(slice))
+    }
+}
@@ -0,0 +1,175 @@
+//! Streaming: decode values from a `std::io::Read` and encode them to a `std::io::Write` one at a time,
+//! without loading the whole input or output into memory.
+//!
+//! Values are read with exactly the bytes they need, so only self-delimiting types can be streamed: a field
+//! without length that reads "the remaining bytes" fails with `NotSelfDelimiting` instead of guessing
+//! where the value ends.
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+};
+
+use crate::{
+    bits::Arena, BitReader, DeserializationError, FromBytesOwned, SerializationConfig, SerializationError, ToBytes,
+};
+
+/// Size of the buffer of `StreamReader`
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Error while reading from or writing to a stream
+#[derive(Debug)]
+pub enum StreamError {
+    /// The reader or writer failed, or the stream ended in the middle of a value (`UnexpectedEof`)
+    Io(io::Error),
+
+    /// A value could not be serialized
+    Serialization(SerializationError),
+
+    /// The bytes could not be deserialized
+    Deserialization(DeserializationError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Serialization(e) => write!(f, "{}", e),
+            Self::Deserialization(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Serialization(e) => Some(e),
+            Self::Deserialization(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<SerializationError> for StreamError {
+    fn from(e: SerializationError) -> Self {
+        Self::Serialization(e)
+    }
+}
+
+impl From<DeserializationError> for StreamError {
+    fn from(e: DeserializationError) -> Self {
+        Self::Deserialization(e)
+    }
+}
+
+/// Codec errors become `InvalidData`, so streaming composes with other `io::Result` code
+impl From<StreamError> for io::Error {
+    fn from(e: StreamError) -> Self {
+        match e {
+            StreamError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// Decode one value from `reader`. Only the bytes of the value are read, so the reader can be used for
+/// the next value, and memory only grows with the bytes that actually arrive. Types that read until the end
+/// of the input (like a `Vec` without length) fail with `NotSelfDelimiting`, in a stream that end is unknown.
+/// Reads are as small as the values, use a `BufReader` or a `StreamReader` for few calls on `reader`.
+pub fn read_from<T: FromBytesOwned, R: Read + ?Sized>(
+    reader: &mut R,
+    config: &SerializationConfig,
+) -> Result<T, StreamError> {
+    let arena = Arena::default();
+    let mut source = reader;
+    let mut bit_reader = BitReader::from_stream(&mut source, &arena);
+    let result = T::from_bytes_internal(&mut bit_reader, config);
+    // Running out of bytes is the stream ending, or failing
+    if let Some(e) = bit_reader.take_io_error() {
+        return Err(StreamError::Io(e));
+    }
+    Ok(result?)
+}
+
+/// Encode one value to `writer`
+pub fn write_to<T: ToBytes + ?Sized, W: Write + ?Sized>(
+    value: &T,
+    writer: &mut W,
+    config: &SerializationConfig,
+) -> Result<(), StreamError> {
+    writer.write_all(&value.to_bytes(config)?)?;
+    Ok(())
+}
+
+/// Buffered reader decoding a stream of values, like the records in a capture file
+pub struct StreamReader<R> {
+    reader: BufReader<R>,
+    config: SerializationConfig,
+}
+
+impl<R: Read> StreamReader<R> {
+    pub fn new(reader: R, config: SerializationConfig) -> Self {
+        Self { reader: BufReader::with_capacity(CHUNK_SIZE, reader), config }
+    }
+
+    /// Decode the next value, or `None` when the stream ends between values
+    pub fn read<T: FromBytesOwned>(&mut self) -> Result<Option<T>, StreamError> {
+        let at_end = loop {
+            match self.reader.fill_buf() {
+                Ok(available) => break available.is_empty(),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        if at_end {
+            return Ok(None);
+        }
+        read_from(&mut self.reader, &self.config).map(Some)
+    }
+
+    /// The bytes read from the reader that are not decoded yet
+    pub fn buffer(&self) -> &[u8] {
+        self.reader.buffer()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+/// Buffered writer encoding a stream of values. Like `BufWriter`, the buffer is flushed when dropped
+pub struct StreamWriter<W: Write> {
+    writer: BufWriter<W>,
+    scratch: Vec<u8>,
+    config: SerializationConfig,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(writer: W, config: SerializationConfig) -> Self {
+        Self { writer: BufWriter::new(writer), scratch: Vec::new(), config }
+    }
+
+    /// Encode a value. Nothing is written when it can't be serialized
+    pub fn write<T: ToBytes + ?Sized>(&mut self, value: &T) -> Result<(), StreamError> {
+        self.scratch.clear();
+        value.to_bytes_into(&mut self.scratch, &self.config)?;
+        self.writer.write_all(&self.scratch)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), StreamError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Flush the buffer and return the writer
+    pub fn into_inner(self) -> Result<W, StreamError> {
+        self.writer.into_inner().map_err(|e| StreamError::Io(e.into_error()))
+    }
+}
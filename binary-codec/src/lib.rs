@@ -1,23 +1,132 @@
-#[derive(Debug)]
-pub enum SerializationError {
-    /// Value is out of bounds (value, min, max)
-    ValueOutOfBounds(i32, i32, i32),
+// Allows the derive macros to refer to `binary_codec::...` from within this crate (tests)
+extern crate self as binary_codec;
 
-    // Unexpected size (expected, actual)
-    UnexpectedLength(usize, usize)
+/// Type that can be serialized to bytes. Can be derived using `#[derive(ToBytes)]`
+pub trait ToBytes {
+    /// Serialize using a writer, continuing at its byte position and bit offset
+    fn to_bytes_internal(&self, writer: &mut BitWriter, config: &SerializationConfig) -> Result<(), SerializationError>;
+
+    /// Serialize to a new byte vector
+    fn to_bytes(&self, config: &SerializationConfig) -> Result<Vec<u8>, SerializationError> {
+        let mut bytes = Vec::new();
+        self.to_bytes_into(&mut bytes, config)?;
+        Ok(bytes)
+    }
+
+    /// Serialize to the end of an existing byte vector, so its allocation can be reused
+    fn to_bytes_into(&self, bytes: &mut Vec<u8>, config: &SerializationConfig) -> Result<(), SerializationError> {
+        self.to_bytes_internal(&mut BitWriter::new(bytes), config)
+    }
+
+    /// Serialize to the start of a slice without allocating, returns the number of bytes written.
+    /// Fails with `BufferTooSmall` if the value doesn't fit
+    fn to_slice(&self, buf: &mut [u8], config: &SerializationConfig) -> Result<usize, SerializationError> {
+        let mut writer = BitWriter::from_slice(buf);
+        self.to_bytes_internal(&mut writer, config)?;
+        Ok(writer.position())
+    }
+
+    /// Serialize to a writer. Use an `io::StreamWriter` to write many values through one buffer
+    fn write_to<W: std::io::Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        config: &SerializationConfig,
+    ) -> Result<(), io::StreamError>
+    where
+        Self: Sized,
+    {
+        io::write_to(self, writer, config)
+    }
+}
+
+/// Type that can be deserialized from bytes. Can be derived using `#[derive(FromBytes)]`.
+/// The lifetime `'de` is the lifetime of the input, so types like `&'de str` can borrow from it
+pub trait FromBytes<'de>: Sized {
+    /// Deserialize using a reader, continuing at its byte position and bit offset
+    fn from_bytes_internal(reader: &mut BitReader<'de>, config: &SerializationConfig) -> Result<Self, DeserializationError>;
+
+    /// Deserialize from the start of a byte slice
+    fn from_bytes(bytes: &'de [u8], config: &SerializationConfig) -> Result<Self, DeserializationError> {
+        Self::from_bytes_internal(&mut BitReader::new(bytes), config)
+    }
+
+    /// Deserialize from the start of a byte slice that may not hold the whole value yet, like a network buffer.
+    /// Running out of bytes is not an error but `Decoded::Incomplete`: append bytes and try again.
+    /// Fields that read until the end of the input (like an `Option` without `toggled_by`) are always
+    /// incomplete here, the end of the bytes so far may not be the end of the value
+    fn try_from_bytes(bytes: &'de [u8], config: &SerializationConfig) -> Result<Decoded<Self>, DeserializationError> {
+        decode_partial(BitReader::with_end(bytes, bits::End::Open), config)
+    }
+}
+
+/// Decode from a reader over bytes that may not hold the whole value yet, see `FromBytes::try_from_bytes`
+pub(crate) fn decode_partial<'de, T: FromBytes<'de>>(
+    mut reader: BitReader<'de>,
+    config: &SerializationConfig,
+) -> Result<Decoded<T>, DeserializationError> {
+    match T::from_bytes_internal(&mut reader, config) {
+        Ok(value) => Ok(Decoded::Complete(value, reader.position())),
+        Err(e) => match e.kind {
+            DeserializationErrorKind::NotEnoughBytes(needed) => Ok(Decoded::Incomplete { needed }),
+            _ => Err(e),
+        },
+    }
 }
 
-#[derive(Debug)]
-pub enum DeserializationError {
-    /// Not enough bytes (bytes missing)
-    NotEnoughBytes(usize),
+/// Result of `FromBytes::try_from_bytes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decoded<T> {
+    /// The value and the number of bytes it used
+    Complete(T, usize),
+
+    /// The input ends before the value. `needed` is the minimum number of bytes to append,
+    /// or `None` when it isn't known (like in a dynamic integer)
+    Incomplete { needed: Option<usize> },
+}
+
+impl<T> Decoded<T> {
+    /// Convert a complete value, keeping the number of bytes
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Decoded<U> {
+        match self {
+            Self::Complete(value, len) => Decoded::Complete(f(value), len),
+            Self::Incomplete { needed } => Decoded::Incomplete { needed },
+        }
+    }
+}
 
-    /// Unknown enum discriminator
-    UnknownDiscriminant(u8)
+/// Type that can be deserialized from bytes of any lifetime, because it doesn't borrow from them.
+/// Use this as bound (`T: FromBytesOwned`) instead of `for<'de> FromBytes<'de>`
+pub trait FromBytesOwned: for<'de> FromBytes<'de> {
+    /// Deserialize from a reader, reading only the bytes of the value. Fails with `NotSelfDelimiting` for
+    /// values that read until the end of the input, see `io::read_from`. Use an `io::StreamReader` to read
+    /// many values
+    fn read_from<R: std::io::Read + ?Sized>(
+        reader: &mut R,
+        config: &SerializationConfig,
+    ) -> Result<Self, io::StreamError> {
+        io::read_from(reader, config)
+    }
 }
+impl<T> FromBytesOwned for T where T: for<'de> FromBytes<'de> {}
 
 mod tests;
+pub mod config;
+pub mod error;
 pub mod serializers;
 pub mod encodings;
 pub mod dyn_int;
-pub use binary_codec_derive::{ToBytes, FromBytes};
\ No newline at end of file
+pub mod bits;
+pub mod io;
+#[cfg(feature = "tokio-codec")]
+pub mod codec;
+mod impls;
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub mod fuzzing;
+pub use binary_codec_derive::{ToBytes, FromBytes};
+pub use error::{DeserializationError, DeserializationErrorKind, PathSegment, SerializationError, SerializationErrorKind};
+pub use bits::{BitReader, BitWriter};
+pub use io::{StreamError, StreamReader, StreamWriter};
+#[cfg(feature = "tokio-codec")]
+pub use codec::{BinaryCodec, DEFAULT_MAX_FRAME_LENGTH};
+pub use config::{BitOrder, BitPacking, ByteOrder, LengthPrefix, SerializationConfig};
@@ -1,79 +1,553 @@
-use crate::{encodings::ZigZag, DeserializationError, SerializationError};
+use crate::{
+    dyn_int,
+    encodings::{f16_bits_to_f32, f32_to_f16_bits, FixedInt, ZigZag},
+    BitOrder, BitPacking, BitReader, BitWriter, DeserializationError, DeserializationErrorKind, LengthPrefix,
+    SerializationConfig, SerializationError, SerializationErrorKind,
+};
 
 pub fn read_small_dynamic_unsigned(
-    bytes: &[u8],
-    pos: &mut usize,
-    bits: &mut u8,
+    reader: &mut BitReader,
     bit_count: u8,
+    config: &SerializationConfig,
 ) -> Result<u8, DeserializationError> {
-    read_small_dynamic(bytes, pos, bits, bit_count)
+    Ok(read_bits(reader, bit_count, config)? as u8)
 }
 
 pub fn read_small_dynamic_signed(
-    bytes: &[u8],
-    pos: &mut usize,
-    bits: &mut u8,
+    reader: &mut BitReader,
     bit_count: u8,
+    config: &SerializationConfig,
 ) -> Result<i8, DeserializationError> {
-    let val = read_small_dynamic(bytes, pos, bits, bit_count)?;
+    let val = read_bits(reader, bit_count, config)? as u8;
     Ok(ZigZag::to_signed(val))
 }
 
 pub fn write_small_dynamic_unsigned(
     val: u8,
-    bytes: &mut Vec<u8>,
-    pos: &mut usize,
-    bits: &mut u8,
+    writer: &mut BitWriter,
     bit_count: u8,
+    config: &SerializationConfig,
 ) -> Result<(), SerializationError> {
     let max = (1u8 << bit_count) - 1;
 
     if val > max {
-        return Err(SerializationError::ValueOutOfBounds(val as i32, 0, max as i32));
+        return Err(SerializationErrorKind::UnsignedOutOfBounds(val as u128, max as u128).into());
     }
 
-    write_small_dynamic(val, bytes, pos, bits, bit_count)
+    write_bits(val as u128, writer, bit_count, config)
 }
 
 pub fn write_small_dynamic_signed(
     val: i8,
-    bytes: &mut Vec<u8>,
-    pos: &mut usize,
-    bits: &mut u8,
+    writer: &mut BitWriter,
     bit_count: u8,
+    config: &SerializationConfig,
 ) -> Result<(), SerializationError> {
     let min = -(1i8 << (bit_count - 1));
     let max = (1i8 << (bit_count - 1)) - 1;
 
     if val < min || val > max {
-        return Err(SerializationError::ValueOutOfBounds(val as i32, min as i32, max as i32));
+        return Err(SerializationErrorKind::ValueOutOfBounds(val as i128, min as i128, max as i128).into());
     }
 
-    write_small_dynamic(val.to_unsigned(), bytes, pos, bits, bit_count)
+    write_bits(val.to_unsigned() as u128, writer, bit_count, config)
 }
 
 pub fn write_bool(
     val: bool,
-    bytes: &mut Vec<u8>,
-    pos: &mut usize,
-    bits: &mut u8
+    writer: &mut BitWriter,
+    config: &SerializationConfig,
 ) -> Result<(), SerializationError> {
     let val_u8 = if val { 1 } else { 0 };
-    write_small_dynamic(val_u8, bytes, pos, bits, 1)
+    write_bits(val_u8, writer, 1, config)
 }
 
 pub fn read_bool(
-    bytes: &[u8],
-    pos: &mut usize,
-    bits: &mut u8
+    reader: &mut BitReader,
+    config: &SerializationConfig,
 ) -> Result<bool, DeserializationError> {
-    let val = read_small_dynamic(bytes, pos, bits, 1)?;
+    let val = read_bits(reader, 1, config)?;
     Ok(val != 0)
 }
 
+/// Write the lowest `bit_count` bits (up to 128) of a value into the bit stream, lowest bits first.
+/// With `BitPacking::Aligned`, values up to 8 bits never straddle a byte boundary (like small dynamic fields),
+/// wider values fill up the current byte and continue in the next bytes.
+/// With `BitPacking::Contiguous`, all values continue in the current byte.
+/// With `BitOrder::Msb`, bytes are filled from the highest bit and values are written highest bits first.
+pub fn write_bits(
+    val: u128,
+    writer: &mut BitWriter,
+    bit_count: u8,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    if !valid_bit_count(bit_count) {
+        return Err(SerializationErrorKind::InvalidBitCount(bit_count).into());
+    }
+
+    let aligned = config.bit_packing == BitPacking::Aligned;
+    let msb_first = config.bit_order == BitOrder::Msb;
+
+    if aligned && !msb_first && bit_count < 8 {
+        return write_small_dynamic(val as u8, writer, bit_count);
+    }
+
+    if aligned && bit_count <= 8 && writer.bits + bit_count > 8 {
+        writer.bits = 0;
+    }
+
+    let mut val = val;
+    let mut remaining = bit_count;
+    while remaining > 0 {
+        if writer.bits == 0 {
+            writer.push_byte()?;
+        }
+
+        // MSB first: the highest bits of the value go into the highest free bits of the byte
+        let chunk = remaining.min(8 - writer.bits);
+        let (part, shift) = if msb_first {
+            ((val >> (remaining - chunk)) as u8, 8 - writer.bits - chunk)
+        } else {
+            (val as u8, writer.bits)
+        };
+        let mask = (((1u16 << chunk) - 1) as u8) << shift;
+        *writer.last_byte() &= !mask;
+        *writer.last_byte() |= (part << shift) & mask;
+
+        if !msb_first {
+            val >>= chunk;
+        }
+        remaining -= chunk;
+        writer.bits = (writer.bits + chunk) % 8;
+    }
+
+    Ok(())
+}
+
+/// Read `bit_count` bits (up to 128) from the bit stream, see `write_bits`
+pub fn read_bits(
+    reader: &mut BitReader,
+    bit_count: u8,
+    config: &SerializationConfig,
+) -> Result<u128, DeserializationError> {
+    if !valid_bit_count(bit_count) {
+        return Err(DeserializationErrorKind::InvalidBitCount(bit_count).into());
+    }
+
+    let aligned = config.bit_packing == BitPacking::Aligned;
+    let msb_first = config.bit_order == BitOrder::Msb;
+
+    if aligned && !msb_first && bit_count < 8 {
+        return Ok(read_small_dynamic(reader, bit_count)? as u128);
+    }
+
+    if aligned && bit_count <= 8 && reader.bits + bit_count > 8 {
+        reader.bits = 0;
+    }
+
+    let mut val = 0u128;
+    let mut read = 0;
+    let mut remaining = bit_count;
+    while remaining > 0 {
+        if reader.bits == 0 {
+            reader.fill(remaining.div_ceil(8) as usize)?;
+            reader.start_byte();
+        }
+
+        let chunk = remaining.min(8 - reader.bits);
+        let shift = if msb_first { 8 - reader.bits - chunk } else { reader.bits };
+        let mask = ((1u16 << chunk) - 1) as u8;
+        let part = ((reader.buffered()[reader.buffer_pos() - 1] >> shift) & mask) as u128;
+
+        if msb_first {
+            val = (val << chunk) | part;
+        } else {
+            val |= part << read;
+        }
+        read += chunk;
+        remaining -= chunk;
+        reader.bits = (reader.bits + chunk) % 8;
+    }
+
+    Ok(val)
+}
+
+/// Write an unsigned integer of `bit_count` bits (up to 128) into the bit stream, see `write_bits`
+pub fn write_bits_unsigned(
+    val: u128,
+    writer: &mut BitWriter,
+    bit_count: u8,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    if !valid_bit_count(bit_count) {
+        return Err(SerializationErrorKind::InvalidBitCount(bit_count).into());
+    }
+
+    let max = u128::MAX >> (128 - bit_count as u32);
+
+    if val > max {
+        return Err(SerializationErrorKind::UnsignedOutOfBounds(val, max).into());
+    }
+
+    write_bits(val, writer, bit_count, config)
+}
+
+pub fn read_bits_unsigned(
+    reader: &mut BitReader,
+    bit_count: u8,
+    config: &SerializationConfig,
+) -> Result<u128, DeserializationError> {
+    read_bits(reader, bit_count, config)
+}
+
+/// Write a signed integer of `bit_count` bits (up to 128) zigzag encoded into the bit stream, see `write_bits`
+pub fn write_bits_signed(
+    val: i128,
+    writer: &mut BitWriter,
+    bit_count: u8,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    if !valid_bit_count(bit_count) {
+        return Err(SerializationErrorKind::InvalidBitCount(bit_count).into());
+    }
+
+    let max = i128::MAX >> (128 - bit_count as u32);
+    let min = -max - 1;
+
+    if val < min || val > max {
+        return Err(SerializationErrorKind::ValueOutOfBounds(val, min, max).into());
+    }
+
+    write_bits(val.to_unsigned(), writer, bit_count, config)
+}
+
+pub fn read_bits_signed(
+    reader: &mut BitReader,
+    bit_count: u8,
+    config: &SerializationConfig,
+) -> Result<i128, DeserializationError> {
+    let val = read_bits(reader, bit_count, config)?;
+    Ok(i128::to_signed(val))
+}
+
+/// Bit fields have 1 to 128 bits
+fn valid_bit_count(bit_count: u8) -> bool {
+    (1..=128).contains(&bit_count)
+}
+
+/// Write constant reserved bits, see `write_bits`
+pub fn write_reserved(
+    value: u128,
+    writer: &mut BitWriter,
+    bit_count: u8,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    write_bits_unsigned(value, writer, bit_count, config)
+}
+
+/// Read reserved bits and check they have the expected value
+pub fn read_reserved(
+    reader: &mut BitReader,
+    bit_count: u8,
+    value: u128,
+    config: &SerializationConfig,
+) -> Result<(), DeserializationError> {
+    let found = read_bits(reader, bit_count, config)?;
+
+    if found != value {
+        return Err(DeserializationErrorKind::InvalidReservedValue(value, found).into());
+    }
+
+    Ok(())
+}
+
+/// Write `bit_count` zero bits. Padding always continues in the current byte, regardless of the bit packing
+pub fn write_padding(
+    writer: &mut BitWriter,
+    bit_count: usize,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    let config = config.with_bit_packing(BitPacking::Contiguous);
+    let mut remaining = bit_count;
+    while remaining > 0 {
+        let chunk = remaining.min(128);
+        write_bits(0, writer, chunk as u8, &config)?;
+        remaining -= chunk;
+    }
+
+    Ok(())
+}
+
+/// Skip `bit_count` padding bits
+pub fn read_padding(
+    reader: &mut BitReader,
+    bit_count: usize,
+    config: &SerializationConfig,
+) -> Result<(), DeserializationError> {
+    let config = config.with_bit_packing(BitPacking::Contiguous);
+    let mut remaining = bit_count;
+    while remaining > 0 {
+        let chunk = remaining.min(128);
+        read_bits(reader, chunk as u8, &config)?;
+        remaining -= chunk;
+    }
+
+    Ok(())
+}
+
+/// Finish the current byte and write zero bytes until the position is a multiple of `align`
+pub fn write_alignment(
+    writer: &mut BitWriter,
+    align: usize,
+    _config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    writer.align();
+    let padding = (align - writer.position() % align) % align;
+    writer.write_zeros(padding)
+}
+
+/// Finish the current byte and skip bytes until the position is a multiple of `align`
+pub fn read_alignment(
+    reader: &mut BitReader,
+    align: usize,
+    _config: &SerializationConfig,
+) -> Result<(), DeserializationError> {
+    let padding = (align - reader.position() % align) % align;
+    reader.read_bytes(padding)?;
+    Ok(())
+}
+
+/// Read `len` bytes, starting at a new byte
+pub fn read_bytes<'a>(
+    reader: &mut BitReader<'a>,
+    len: usize,
+    _config: &SerializationConfig,
+) -> Result<&'a [u8], DeserializationError> {
+    reader.read_bytes(len)
+}
+
+/// Write bytes like a sequence of u8 fields: starting at a new byte, unless the slice is empty
+pub fn write_byte_slice(
+    val: &[u8],
+    writer: &mut BitWriter,
+    _config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    if val.is_empty() {
+        return Ok(());
+    }
+    writer.write_bytes(val)
+}
+
+/// Read `len` bytes like a sequence of u8 fields: starting at a new byte, unless `len` is 0
+pub fn read_byte_slice<'a>(
+    reader: &mut BitReader<'a>,
+    len: usize,
+    _config: &SerializationConfig,
+) -> Result<&'a [u8], DeserializationError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    reader.read_bytes(len)
+}
+
+/// Read all bytes left, starting at a new byte. Fails where the end of the input isn't known, see
+/// `BitReader::has_remaining`
+pub fn read_remaining<'a>(
+    reader: &mut BitReader<'a>,
+    _config: &SerializationConfig,
+) -> Result<&'a [u8], DeserializationError> {
+    reader.read_remaining()
+}
+
+/// Read a dynamic integer, starting at a new byte
+pub fn read_dyn_int(reader: &mut BitReader, _config: &SerializationConfig) -> Result<u128, DeserializationError> {
+    reader.read_dyn_int()
+}
+
+/// Write magic bytes, starting at a new byte
+pub fn write_magic(
+    magic: &[u8],
+    writer: &mut BitWriter,
+    _config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    writer.write_bytes(magic)
+}
+
+/// Read magic bytes and check they match
+pub fn read_magic(
+    reader: &mut BitReader,
+    magic: &[u8],
+    _config: &SerializationConfig,
+) -> Result<(), DeserializationError> {
+    let found = reader.read_bytes(magic.len())?;
+    if found != magic {
+        return Err(DeserializationErrorKind::InvalidMagic {
+            expected: magic.to_vec(),
+            found: found.to_vec(),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Write a float as IEEE-754 half float (16 bits) into the bit stream
+pub fn write_f16(
+    val: f32,
+    writer: &mut BitWriter,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    write_bits(f32_to_f16_bits(val) as u128, writer, 16, config)
+}
+
+pub fn read_f16(
+    reader: &mut BitReader,
+    config: &SerializationConfig,
+) -> Result<f32, DeserializationError> {
+    let raw = read_bits(reader, 16, config)?;
+    Ok(f16_bits_to_f32(raw as u16))
+}
+
+/// Write a float as fixed point integer of `bit_count` bits (up to 64): `round(val * scale)`.
+/// Signed values are zigzag encoded.
+pub fn write_fixed_point(
+    val: f64,
+    writer: &mut BitWriter,
+    scale: f64,
+    bit_count: u8,
+    signed: bool,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    let scaled = (val * scale).round();
+    let (min, max) = if signed {
+        let half = f64::powi(2.0, bit_count as i32 - 1);
+        (-half, half - 1.0)
+    } else {
+        (0.0, f64::powi(2.0, bit_count as i32) - 1.0)
+    };
+
+    // Also rejects NaN
+    if !(scaled >= min && scaled <= max) {
+        return Err(SerializationErrorKind::ValueOutOfBounds(scaled as i128, min as i128, max as i128).into());
+    }
+
+    let raw = if signed {
+        (scaled as i64).to_unsigned() as u128
+    } else {
+        scaled as u128
+    };
+
+    write_bits(raw, writer, bit_count, config)
+}
+
+pub fn read_fixed_point(
+    reader: &mut BitReader,
+    scale: f64,
+    bit_count: u8,
+    signed: bool,
+    config: &SerializationConfig,
+) -> Result<f64, DeserializationError> {
+    let raw = read_bits(reader, bit_count, config)?;
+    let val = if signed {
+        i64::to_signed(raw as u64) as f64
+    } else {
+        raw as f64
+    };
+
+    Ok(val / scale)
+}
+
+/// Write a length prefix using the configured length prefix strategy
+pub fn write_length(
+    len: usize,
+    writer: &mut BitWriter,
+    config: &SerializationConfig,
+) -> Result<(), SerializationError> {
+    if len > config.max_length {
+        return Err(SerializationErrorKind::LengthLimitExceeded(len, config.max_length).into());
+    }
+
+    match config.length_prefix {
+        LengthPrefix::Dynamic => writer.write_dyn_int(len as u128),
+        LengthPrefix::U8 => {
+            let len = u8::try_from(len).map_err(|_| {
+                SerializationErrorKind::UnsignedOutOfBounds(len as u128, u8::MAX as u128)
+            })?;
+            len.write(writer, config)
+        }
+        LengthPrefix::U16 => {
+            let len = u16::try_from(len).map_err(|_| {
+                SerializationErrorKind::UnsignedOutOfBounds(len as u128, u16::MAX as u128)
+            })?;
+            len.write(writer, config)
+        }
+        LengthPrefix::U32 => {
+            let len = u32::try_from(len).map_err(|_| {
+                SerializationErrorKind::UnsignedOutOfBounds(len as u128, u32::MAX as u128)
+            })?;
+            len.write(writer, config)
+        }
+    }
+}
+
+/// Read a length prefix using the configured length prefix strategy
+pub fn read_length(
+    reader: &mut BitReader,
+    config: &SerializationConfig,
+) -> Result<usize, DeserializationError> {
+    let len = match config.length_prefix {
+        LengthPrefix::Dynamic => {
+            let len = reader.read_dyn_int()?;
+            usize::try_from(len).unwrap_or(usize::MAX)
+        }
+        LengthPrefix::U8 => u8::read(reader, config)? as usize,
+        LengthPrefix::U16 => u16::read(reader, config)? as usize,
+        LengthPrefix::U32 => u32::read(reader, config)? as usize,
+    };
+
+    check_length(len, config)
+}
+
+/// Number of bytes of the length prefix for `len`
+pub fn length_prefix_size(len: usize, config: &SerializationConfig) -> usize {
+    match config.length_prefix {
+        LengthPrefix::Dynamic => dyn_int::encoded_size(len as u128),
+        LengthPrefix::U8 => 1,
+        LengthPrefix::U16 => 2,
+        LengthPrefix::U32 => 4,
+    }
+}
+
+/// Validate a length read from the input against the configured maximum
+pub fn check_length(len: usize, config: &SerializationConfig) -> Result<usize, DeserializationError> {
+    if len > config.max_length {
+        return Err(DeserializationErrorKind::LengthLimitExceeded(len, config.max_length).into());
+    }
+
+    Ok(len)
+}
+
+/// Capacity to reserve for `len` elements of `T`. The length comes from the input, so the allocation
+/// is capped by the bytes left in it: the elements can't take more memory than that before they are decoded
+pub fn element_capacity<T>(len: usize, reader: &BitReader) -> usize {
+    len.min(reader.remaining_len() / std::mem::size_of::<T>().max(1))
+}
+
+/// Check the first of `len` elements, which started at `start`. An element that used no input (like a unit struct)
+/// leaves the reader as it was, so all others decode the same way: a count larger than the bytes left is rejected,
+/// instead of looping or allocating for it
+pub fn check_element_progress(reader: &BitReader, start: (usize, u8), len: usize) -> Result<(), DeserializationError> {
+    let remaining = reader.remaining_len();
+    if (reader.position(), reader.bit_offset()) == start && len > remaining {
+        return Err(DeserializationErrorKind::LengthLimitExceeded(len, remaining).into());
+    }
+
+    Ok(())
+}
+
 fn create_mask(bits: &u8, bit_count: u8) -> u8 {
     let mask = (1u8 << bit_count) - 1u8;
-    return mask << *bits;
+    mask << *bits
 }
 
 // returns (bits, next_bits, next_byte)
@@ -89,48 +563,46 @@ fn next_bits_and_byte(bits: u8, bits_needed: u8) -> (u8, u8, bool) {
 }
 
 fn read_small_dynamic(
-    bytes: &[u8],
-    pos: &mut usize,
-    bits: &mut u8,
+    reader: &mut BitReader,
     bit_count: u8
 ) -> Result<u8, DeserializationError>
 {
-    let (current_bits, next_bits, next_byte) = next_bits_and_byte(*bits, bit_count);
+    let (current_bits, next_bits, next_byte) = next_bits_and_byte(reader.bits, bit_count);
     let mask = create_mask(&current_bits, bit_count);
 
     if next_byte {
-        *pos += 1;
+        reader.fill(1)?;
+        reader.start_byte();
     }
 
-    let read_pos = if *pos == 0 { 0 } else { *pos - 1 };
+    let read_pos = reader.buffer_pos().saturating_sub(1);
 
-    let val = bytes[read_pos];
+    let Some(&val) = reader.buffered().get(read_pos) else {
+        return Err(DeserializationErrorKind::NotEnoughBytes(Some(1)).into());
+    };
     let result = (val & mask) >> current_bits;
 
-    *bits = next_bits;
+    reader.bits = next_bits;
     Ok(result)
 }
 
 fn write_small_dynamic(
     val: u8,
-    bytes: &mut Vec<u8>,
-    pos: &mut usize,
-    bits: &mut u8,
+    writer: &mut BitWriter,
     bit_count: u8
 ) -> Result<(), SerializationError>
 {
-    let (current_bits, next_bits, next_byte) = next_bits_and_byte(*bits, bit_count);
+    let (current_bits, next_bits, next_byte) = next_bits_and_byte(writer.bits, bit_count);
     let mask = create_mask(&current_bits, bit_count);
 
     if next_byte {
-        bytes.push(0u8);
-        *pos += 1;
+        writer.push_byte()?;
     }
 
-    bytes[*pos - 1] &= !mask;
-    bytes[*pos - 1] |= (val << current_bits) & mask;
+    *writer.last_byte() &= !mask;
+    *writer.last_byte() |= (val << current_bits) & mask;
 
-    *bits = next_bits;
+    writer.bits = next_bits;
     Ok(())
 }
 
@@ -141,92 +613,88 @@ mod tests {
     #[test]
     fn test_write_read_small_dynamic_unsigned() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
         let bit_count = 4;
         let val: u8 = 0b1010;
-        write_small_dynamic_unsigned(val, &mut bytes, &mut pos, &mut bits, bit_count).unwrap();
-        pos = 0;
-        bits = 0;
-        let result = read_small_dynamic_unsigned(&bytes, &mut pos, &mut bits, bit_count).unwrap();
+        write_small_dynamic_unsigned(val, &mut writer, bit_count, &config).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        let result = read_small_dynamic_unsigned(&mut reader, bit_count, &config).unwrap();
         assert_eq!(result, val);
     }
 
     #[test]
     fn test_write_read_small_dynamic_signed() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
         let bit_count = 4;
         let val: i8 = -3;
-        write_small_dynamic_signed(val, &mut bytes, &mut pos, &mut bits, bit_count).unwrap();
-        pos = 0;
-        bits = 0;
-        let result = read_small_dynamic_signed(&bytes, &mut pos, &mut bits, bit_count).unwrap();
+        write_small_dynamic_signed(val, &mut writer, bit_count, &config).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        let result = read_small_dynamic_signed(&mut reader, bit_count, &config).unwrap();
         assert_eq!(result, val);
     }
 
     #[test]
     fn test_write_read_small_dynamic_unsigned_existing_byte() {
-        let mut bytes = vec![0b0000_0111]; // 7
-        let mut pos = 1;
-        let mut bits = 4;
+        let mut bytes = Vec::new();
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
         let bit_count = 4;
-        let val: u8 = 5;
-        write_small_dynamic_unsigned(val, &mut bytes, &mut pos, &mut bits, bit_count).unwrap();
+        write_small_dynamic_unsigned(7, &mut writer, bit_count, &config).unwrap();
+        assert_eq!((writer.position(), writer.bit_offset()), (1, 4));
+        write_small_dynamic_unsigned(5, &mut writer, bit_count, &config).unwrap();
         assert_eq!(bytes, vec![0b0101_0111]);
-        pos = 0;
-        bits = 0;
-        let result = read_small_dynamic_unsigned(&bytes, &mut pos, &mut bits, bit_count).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        let result = read_small_dynamic_unsigned(&mut reader, bit_count, &config).unwrap();
         assert_eq!(result, 7); // first 4 bits
-        let result = read_small_dynamic_unsigned(&bytes, &mut pos, &mut bits, bit_count).unwrap();
+        let result = read_small_dynamic_unsigned(&mut reader, bit_count, &config).unwrap();
         assert_eq!(result, 5); // last 4 bits
     }
 
     #[test]
     fn test_write_small_dynamic_unsigned_out_of_bounds() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
         let bit_count = 3;
         let val: u8 = 0b1000; // 8, out of bounds for 3 bits
-        let result = write_small_dynamic_unsigned(val, &mut bytes, &mut pos, &mut bits, bit_count);
+        let result = write_small_dynamic_unsigned(val, &mut writer, bit_count, &config);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_write_small_dynamic_signed_out_of_bounds() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
         let bit_count = 3;
         let val: i8 = 5; // out of bounds for 3 bits signed
-        let result = write_small_dynamic_signed(val, &mut bytes, &mut pos, &mut bits, bit_count);
+        let result = write_small_dynamic_signed(val, &mut writer, bit_count, &config);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_write_read_bool_true() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
-        write_bool(true, &mut bytes, &mut pos, &mut bits).unwrap();
-        pos = 0;
-        bits = 0;
-        let result = read_bool(&bytes, &mut pos, &mut bits).unwrap();
-        assert_eq!(result, true);
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
+        write_bool(true, &mut writer, &config).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        let result = read_bool(&mut reader, &config).unwrap();
+        assert!(result);
     }
 
     #[test]
     fn test_write_read_bool_false() {
         let mut bytes = Vec::new();
-        let mut pos = 0;
-        let mut bits = 0;
-        write_bool(false, &mut bytes, &mut pos, &mut bits).unwrap();
-        pos = 0;
-        bits = 0;
-        let result = read_bool(&bytes, &mut pos, &mut bits).unwrap();
-        assert_eq!(result, false);
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
+        write_bool(false, &mut writer, &config).unwrap();
+        let mut reader = BitReader::new(&bytes);
+        let result = read_bool(&mut reader, &config).unwrap();
+        assert!(!result);
     }
 
     #[test]
@@ -256,4 +724,138 @@ mod tests {
         let (bits, next_bits, next_byte) = next_bits_and_byte(4, 4);
         assert_eq!((bits, next_bits, next_byte), (4, 0, false));
     }
+
+    #[test]
+    fn test_write_read_length_prefixes() {
+        for length_prefix in [LengthPrefix::Dynamic, LengthPrefix::U8, LengthPrefix::U16, LengthPrefix::U32] {
+            let config = SerializationConfig { length_prefix, ..Default::default() };
+            for len in [0usize, 1, 200] {
+                let mut bytes = Vec::new();
+                write_length(len, &mut BitWriter::new(&mut bytes), &config).unwrap();
+                let mut reader = BitReader::new(&bytes);
+                assert_eq!(read_length(&mut reader, &config).unwrap(), len);
+                assert_eq!(reader.position(), bytes.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_length_prefix_overflow_and_limit() {
+        let mut bytes = Vec::new();
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig { length_prefix: LengthPrefix::U8, ..Default::default() };
+        assert!(write_length(256, &mut writer, &config).is_err());
+
+        let config = SerializationConfig { max_length: 10, ..Default::default() };
+        assert!(write_length(11, &mut writer, &config).is_err());
+
+        let bytes = vec![11];
+        assert_eq!(
+            read_length(&mut BitReader::new(&bytes), &config).unwrap_err().kind,
+            DeserializationErrorKind::LengthLimitExceeded(11, 10)
+        );
+    }
+
+    #[test]
+    fn test_write_read_bits_straddling() {
+        let mut bytes = Vec::new();
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
+        write_bits(0b101, &mut writer, 3, &config).unwrap();
+        write_bits(0xabc, &mut writer, 12, &config).unwrap();
+        write_bits(0b11, &mut writer, 2, &config).unwrap();
+        assert_eq!(writer.written(), [0b1110_0101, 0b0101_0101, 0b0000_0011]);
+        assert_eq!((writer.position(), writer.bit_offset()), (3, 2));
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(read_bits(&mut reader, 3, &config).unwrap(), 0b101);
+        assert_eq!(read_bits(&mut reader, 12, &config).unwrap(), 0xabc);
+        assert_eq!(read_bits(&mut reader, 2, &config).unwrap(), 0b11);
+        assert_eq!(
+            read_bits(&mut reader, 16, &config).unwrap_err().kind,
+            DeserializationErrorKind::NotEnoughBytes(Some(2))
+        );
+    }
+
+    #[test]
+    fn test_write_read_fixed_point() {
+        let mut bytes = Vec::new();
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
+        write_fixed_point(-12.34, &mut writer, 100.0, 12, true, &config).unwrap();
+        write_fixed_point(3.5, &mut writer, 2.0, 4, false, &config).unwrap();
+        assert_eq!(writer.position(), 2);
+        assert_eq!(
+            write_fixed_point(20.48, &mut writer, 100.0, 12, true, &config).unwrap_err().kind,
+            SerializationErrorKind::ValueOutOfBounds(2048, -2048, 2047)
+        );
+        assert!(write_fixed_point(-0.5, &mut writer, 2.0, 4, false, &config).is_err());
+        assert_eq!(bytes.len(), 2);
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(read_fixed_point(&mut reader, 100.0, 12, true, &config).unwrap(), -12.34);
+        assert_eq!(read_fixed_point(&mut reader, 2.0, 4, false, &config).unwrap(), 3.5);
+    }
+
+    #[test]
+    fn test_write_read_wide_bits() {
+        let mut bytes = Vec::new();
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
+        write_bits_unsigned(0x5, &mut writer, 4, &config).unwrap();
+        write_bits_unsigned(0xfff, &mut writer, 12, &config).unwrap();
+        write_bits_signed(-300_000, &mut writer, 20, &config).unwrap();
+        write_bits_unsigned(u128::MAX, &mut writer, 128, &config).unwrap();
+        write_bits_signed(i128::MIN, &mut writer, 128, &config).unwrap();
+        assert_eq!(bytes.len(), (4 + 12 + 20 + 128 + 128usize).div_ceil(8));
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(read_bits_unsigned(&mut reader, 4, &config).unwrap(), 0x5);
+        assert_eq!(read_bits_unsigned(&mut reader, 12, &config).unwrap(), 0xfff);
+        assert_eq!(read_bits_signed(&mut reader, 20, &config).unwrap(), -300_000);
+        assert_eq!(read_bits_unsigned(&mut reader, 128, &config).unwrap(), u128::MAX);
+        assert_eq!(read_bits_signed(&mut reader, 128, &config).unwrap(), i128::MIN);
+    }
+
+    #[test]
+    fn test_write_bits_out_of_bounds() {
+        let mut bytes = Vec::new();
+        let mut writer = BitWriter::new(&mut bytes);
+        let config = SerializationConfig::default();
+        assert_eq!(
+            write_bits_unsigned(0x1000, &mut writer, 12, &config).unwrap_err().kind,
+            SerializationErrorKind::UnsignedOutOfBounds(4096, 4095)
+        );
+        assert_eq!(
+            write_bits_signed(-524_289, &mut writer, 20, &config).unwrap_err().kind,
+            SerializationErrorKind::ValueOutOfBounds(-524_289, -524_288, 524_287)
+        );
+        // Wide values are not truncated
+        assert_eq!(
+            write_bits_unsigned(u64::MAX as u128, &mut writer, 40, &config).unwrap_err().kind,
+            SerializationErrorKind::UnsignedOutOfBounds(u64::MAX as u128, (1 << 40) - 1)
+        );
+        assert_eq!(
+            write_bits_unsigned(u128::MAX, &mut writer, 127, &config).unwrap_err().kind,
+            SerializationErrorKind::UnsignedOutOfBounds(u128::MAX, u128::MAX >> 1)
+        );
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_read_past_end() {
+        let config = SerializationConfig::default();
+        let bytes = [0b1010_1010];
+        assert!(read_small_dynamic_unsigned(&mut BitReader::new(&[]), 4, &config).is_err());
+        assert_eq!(
+            read_bytes(&mut BitReader::new(&bytes), 3, &config).unwrap_err().kind,
+            DeserializationErrorKind::NotEnoughBytes(Some(2))
+        );
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(read_bytes(&mut reader, 1, &config).unwrap(), bytes);
+        assert!(read_bool(&mut reader, &config).is_err());
+        assert!(read_dyn_int(&mut reader, &config).is_err());
+        assert!(read_remaining(&mut reader, &config).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,120 @@
+//! Memory used while decoding lengths from untrusted input, measured with a counting allocator.
+//! The counters are per thread, so tests running in parallel don't disturb each other.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use crate::{DeserializationErrorKind, FromBytes, SerializationConfig, ToBytes};
+
+struct Counting;
+
+thread_local! {
+    static CURRENT: Cell<usize> = const { Cell::new(0) };
+    static PEAK: Cell<usize> = const { Cell::new(0) };
+}
+
+fn track(added: usize, removed: usize) {
+    // Fails while the thread is torn down, those allocations don't matter
+    let _ = CURRENT.try_with(|current| {
+        current.set((current.get() + added).saturating_sub(removed));
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(current.get())));
+    });
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        track(layout.size(), 0);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track(0, layout.size());
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        track(new_size, layout.size());
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Largest number of bytes allocated at once while running `f`, on top of what was allocated before
+fn peak_allocation(f: impl FnOnce()) -> usize {
+    let start = CURRENT.with(Cell::get);
+    PEAK.with(|peak| peak.set(start));
+    f();
+    PEAK.with(Cell::get) - start
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Blocks {
+    #[dynamic_len]
+    items: Vec<[u64; 128]>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Table {
+    #[dynamic_len]
+    entries: HashMap<u64, [u64; 16]>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Unit;
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Units {
+    #[dynamic_len]
+    units: Vec<Unit>,
+    #[dynamic_len]
+    options: Vec<Option<u64>>,
+}
+
+#[test]
+fn test_claimed_length_allocates_by_input_size() {
+    let config = SerializationConfig::default();
+    // Claims 2^32 elements, followed by about 200 KB of input
+    let mut input = crate::dyn_int::encode(1 << 32);
+    input.resize(200_000, 0);
+
+    let peak = peak_allocation(|| {
+        let error = Blocks::from_bytes(&input, &config).unwrap_err();
+        assert!(matches!(error.kind, DeserializationErrorKind::NotEnoughBytes(_)));
+    });
+    assert!(peak <= input.len() + 4096, "{} bytes allocated for {} bytes of input", peak, input.len());
+
+    // A hash table takes a few times the memory of its entries
+    let peak = peak_allocation(|| {
+        assert!(Table::from_bytes(&input, &config).is_err());
+    });
+    assert!(peak <= 3 * input.len(), "{} bytes allocated for {} bytes of input", peak, input.len());
+
+    // Honest values still decode
+    let blocks = Blocks { items: vec![[7; 128]; 3] };
+    assert_eq!(Blocks::from_bytes(&blocks.to_bytes(&config).unwrap(), &config).unwrap(), blocks);
+}
+
+#[test]
+fn test_claimed_length_of_elements_without_input() {
+    let config = SerializationConfig::default();
+    let units = Units { units: vec![Unit, Unit], options: vec![Some(1)] };
+    assert_eq!(Units::from_bytes(&units.to_bytes(&config).unwrap(), &config).unwrap(), units);
+
+    // Unit structs use no input, so only as many as there are bytes left are accepted instead of 2^60
+    let mut input = crate::dyn_int::encode(1 << 60);
+    input.extend([0; 3]);
+    let error = Units::from_bytes(&input, &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::LengthLimitExceeded(1 << 60, 3));
+
+    // Options at the end of the input are None without using it
+    let mut input = vec![0];
+    input.extend(crate::dyn_int::encode(1 << 40));
+    let peak = peak_allocation(|| {
+        let error = Units::from_bytes(&input, &config).unwrap_err();
+        assert_eq!(error.kind, DeserializationErrorKind::LengthLimitExceeded(1 << 40, 0));
+    });
+    assert!(peak <= 4096, "{} bytes allocated", peak);
+}
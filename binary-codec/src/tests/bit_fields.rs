@@ -0,0 +1,220 @@
+use crate::{BitOrder, BitPacking, DeserializationErrorKind, FromBytes, SerializationConfig, SerializationErrorKind, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct CanHeader {
+    #[bits = 11]
+    id: u16,
+    rtr: bool,
+    ide: bool,
+    #[bits = 4]
+    dlc: u8,
+    #[bits = 20]
+    offset: i32,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct WideFields {
+    #[bits = 8]
+    a: u8,
+    #[bits = 33]
+    b: u64,
+    #[bits = 100]
+    c: i128,
+    #[bits = 12]
+    values: Vec<u16>,
+}
+
+#[test]
+fn test_bits_straddling_bytes() {
+    let config = SerializationConfig::default();
+    let value = CanHeader { id: 0x123, rtr: true, ide: false, dlc: 8, offset: -5 };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0x23, 0b0000_1001, 0x98, 0, 0]);
+    assert_eq!(CanHeader::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[test]
+fn test_wide_bit_fields() {
+    let config = SerializationConfig::default();
+    let value = WideFields {
+        a: 255,
+        b: (1 << 33) - 1,
+        c: -(1 << 99),
+        values: vec![0xfff, 0, 0xabc],
+    };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes.len(), (8 + 33 + 100 + 3 * 12usize).div_ceil(8));
+    assert_eq!(WideFields::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[test]
+fn test_bits_out_of_bounds() {
+    let config = SerializationConfig::default();
+    let value = CanHeader { id: 0x800, rtr: false, ide: false, dlc: 0, offset: 0 };
+    assert_eq!(value.to_bytes(&config).unwrap_err().kind, SerializationErrorKind::UnsignedOutOfBounds(2048, 2047));
+
+    let value = CanHeader { id: 0, rtr: false, ide: false, dlc: 0, offset: 1 << 19 };
+    assert_eq!(
+        value.to_bytes(&config).unwrap_err().kind,
+        SerializationErrorKind::ValueOutOfBounds(524_288, -524_288, 524_287)
+    );
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[disc_type = "u16"]
+#[disc_bits = 12]
+enum Command {
+    #[disc = 0x0ab]
+    Ping,
+    #[disc = 0xfff]
+    Set(#[bits = 4] u8),
+}
+
+#[test]
+fn test_wide_disc_bits() {
+    let config = SerializationConfig::default();
+    let bytes = Command::Set(5).to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0xff, 0x5f]);
+    assert_eq!(Command::from_bytes(&bytes, &config).unwrap(), Command::Set(5));
+
+    let bytes = Command::Ping.to_bytes(&config).unwrap();
+    assert_eq!(Command::from_bytes(&bytes, &config).unwrap(), Command::Ping);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Flags {
+    #[bits = 6]
+    a: u8,
+    #[bits = 3]
+    b: u8,
+    #[bits = 7]
+    c: u8,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[bit_packing = "contiguous"]
+struct PackedFlags {
+    #[bits = 6]
+    a: u8,
+    #[bits = 3]
+    b: u8,
+    #[bits = 7]
+    c: u8,
+}
+
+#[test]
+fn test_contiguous_bit_packing() {
+    let config = SerializationConfig::default();
+    let value = Flags { a: 0b111111, b: 0b101, c: 0b1000001 };
+    assert_eq!(value.to_bytes(&config).unwrap(), vec![0b0011_1111, 0b0000_0101, 0b0100_0001]);
+
+    let contiguous = config.with_bit_packing(BitPacking::Contiguous);
+    let bytes = value.to_bytes(&contiguous).unwrap();
+    assert_eq!(bytes, vec![0b0111_1111, 0b1000_0011]);
+    assert_eq!(Flags::from_bytes(&bytes, &contiguous).unwrap(), value);
+
+    // Same layout when opted in by the container
+    let value = PackedFlags { a: 0b111111, b: 0b101, c: 0b1000001 };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0b0111_1111, 0b1000_0011]);
+    assert_eq!(PackedFlags::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[bit_order = "msb"]
+struct Ipv4Header {
+    #[bits = 4]
+    version: u8,
+    #[bits = 4]
+    ihl: u8,
+    #[bits = 6]
+    dscp: u8,
+    #[bits = 2]
+    ecn: u8,
+    total_length: u16,
+    identification: u16,
+    reserved: bool,
+    dont_fragment: bool,
+    more_fragments: bool,
+    #[bits = 13]
+    fragment_offset: u16,
+    ttl: u8,
+    protocol: u8,
+    checksum: u16,
+    source: [u8; 4],
+    destination: [u8; 4],
+}
+
+#[test]
+fn test_msb_first_ipv4_header() {
+    let config = SerializationConfig::default();
+    let bytes = vec![
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00,
+        0x01, 0xc0, 0xa8, 0x00, 0xc7,
+    ];
+    let header = Ipv4Header::from_bytes(&bytes, &config).unwrap();
+    assert_eq!(
+        header,
+        Ipv4Header {
+            version: 4,
+            ihl: 5,
+            dscp: 0,
+            ecn: 0,
+            total_length: 0x73,
+            identification: 0,
+            reserved: false,
+            dont_fragment: true,
+            more_fragments: false,
+            fragment_offset: 0,
+            ttl: 64,
+            protocol: 17,
+            checksum: 0xb861,
+            source: [192, 168, 0, 1],
+            destination: [192, 168, 0, 199],
+        }
+    );
+    assert_eq!(header.to_bytes(&config).unwrap(), bytes);
+}
+
+#[test]
+fn test_msb_first_contiguous() {
+    let config = SerializationConfig::default()
+        .with_bit_packing(BitPacking::Contiguous)
+        .with_bit_order(BitOrder::Msb);
+    let value = Flags { a: 0b111111, b: 0b101, c: 0b1000001 };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0b1111_1110, 0b1100_0001]);
+    assert_eq!(Flags::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Layout {
+    #[bits = 3]
+    kind: u8,
+    #[reserved(bits = 2, value = 0b10)]
+    reserved: (),
+    #[pad_bits = 2]
+    flag: bool,
+    #[pad_bits = 12]
+    #[bits = 4]
+    level: u8,
+    #[align = 4]
+    length: u16,
+}
+
+#[test]
+fn test_padding_reserved_and_alignment() {
+    let config = SerializationConfig::default();
+    let value = Layout { kind: 0b101, reserved: (), flag: true, level: 0b1111, length: 0x0102 };
+    let bytes = value.to_bytes(&config).unwrap();
+    // kind | reserved | 2 pad bits | flag, 12 pad bits, level in 4 bits, 1 byte to align to 4, length
+    assert_eq!(bytes, vec![0b1001_0101, 0b0000_0000, 0b1111_0000, 0, 1, 2]);
+    assert_eq!(Layout::from_bytes(&bytes, &config).unwrap(), value);
+
+    let mut invalid = bytes.clone();
+    invalid[0] = 0b1000_1101;
+    assert_eq!(
+        Layout::from_bytes(&invalid, &config).unwrap_err().kind,
+        DeserializationErrorKind::InvalidReservedValue(0b10, 0b01)
+    );
+}
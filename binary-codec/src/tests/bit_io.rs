@@ -0,0 +1,151 @@
+use crate::{
+    BitReader, BitWriter, DeserializationError, DeserializationErrorKind, FromBytes, SerializationConfig,
+    SerializationError, SerializationErrorKind, ToBytes,
+};
+
+/// Derived layout, the hand-written `Manual` below should produce the same bytes
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Derived {
+    #[bits = 3]
+    level: u8,
+    on: bool,
+    #[bits = 5]
+    offset: i8,
+    delta: i16,
+    #[dynamic]
+    count: u32,
+    #[dynamic_len]
+    name: String,
+    id: u32,
+}
+
+#[derive(Debug, PartialEq)]
+struct Manual {
+    level: u8,
+    on: bool,
+    offset: i8,
+    delta: i16,
+    count: u32,
+    name: String,
+    id: u32,
+}
+
+impl ToBytes for Manual {
+    fn to_bytes_internal(&self, writer: &mut BitWriter, config: &SerializationConfig) -> Result<(), SerializationError> {
+        writer.write_bits(self.level as u128, 3, config)?;
+        writer.write_bool(self.on, config)?;
+        writer.write_signed_bits(self.offset as i128, 5, config)?;
+        writer.write_zigzag(self.delta, config)?;
+        writer.write_dyn_int(self.count as u128)?;
+        writer.write_length(self.name.len(), config)?;
+        writer.write_bytes(self.name.as_bytes())?;
+        writer.write_fixed(self.id, config)
+    }
+}
+
+impl FromBytes<'_> for Manual {
+    fn from_bytes_internal(reader: &mut BitReader, config: &SerializationConfig) -> Result<Self, DeserializationError> {
+        let level = reader.read_bits(3, config)? as u8;
+        let on = reader.read_bool(config)?;
+        let offset = reader.read_signed_bits(5, config)? as i8;
+        let delta = reader.read_zigzag(config)?;
+        let count = reader.read_dyn_int()? as u32;
+        let len = reader.read_length(config)?;
+        let name = String::from_utf8(reader.read_bytes(len)?.to_vec())
+            .map_err(|_| DeserializationErrorKind::InvalidUtf8)?;
+        let id = reader.read_fixed(config)?;
+        Ok(Manual { level, on, offset, delta, count, name, id })
+    }
+}
+
+#[test]
+fn test_hand_written_impl_matches_derive() {
+    let config = SerializationConfig::default();
+    let derived = Derived { level: 5, on: true, offset: -7, delta: -300, count: 1000, name: "abc".into(), id: 42 };
+    let manual = Manual { level: 5, on: true, offset: -7, delta: -300, count: 1000, name: "abc".into(), id: 42 };
+
+    let bytes = derived.to_bytes(&config).unwrap();
+    assert_eq!(manual.to_bytes(&config).unwrap(), bytes);
+    assert_eq!(Manual::from_bytes(&bytes, &config).unwrap(), manual);
+}
+
+#[test]
+fn test_writer_and_reader_position() {
+    let config = SerializationConfig::default();
+    let mut bytes = vec![0xff];
+    let mut writer = BitWriter::new(&mut bytes);
+    assert_eq!((writer.position(), writer.bit_offset()), (0, 0));
+    writer.write_bits(0b101, 3, &config).unwrap();
+    writer.write_bool(true, &config).unwrap();
+    assert_eq!((writer.position(), writer.bit_offset()), (1, 4));
+    writer.align();
+    writer.write_bool(true, &config).unwrap();
+    assert_eq!((writer.position(), writer.bit_offset()), (2, 1));
+    // Positions are relative to the start of the writer, existing bytes are kept
+    assert_eq!(writer.written(), [0b1101, 0b1]);
+    assert_eq!(bytes, vec![0xff, 0b1101, 0b1]);
+
+    let mut reader = BitReader::new(&bytes[1..]);
+    assert_eq!(reader.read_bits(3, &config).unwrap(), 0b101);
+    assert_eq!((reader.position(), reader.bit_offset(), reader.remaining_len()), (1, 3, 1));
+    reader.align();
+    assert!(reader.read_bool(&config).unwrap());
+    assert_eq!(reader.read_remaining().unwrap(), []);
+    assert_eq!(reader.read_bytes(1).unwrap_err().kind, DeserializationErrorKind::NotEnoughBytes(Some(1)));
+}
+
+#[test]
+fn test_invalid_bit_count() {
+    let config = SerializationConfig::default();
+    let mut bytes = Vec::new();
+    let mut writer = BitWriter::new(&mut bytes);
+    for count in [0, 129, 200] {
+        assert_eq!(writer.write_bits(0, count, &config).unwrap_err().kind, SerializationErrorKind::InvalidBitCount(count));
+        assert_eq!(
+            writer.write_signed_bits(0, count, &config).unwrap_err().kind,
+            SerializationErrorKind::InvalidBitCount(count)
+        );
+    }
+    assert_eq!(writer.position(), 0);
+
+    let mut reader = BitReader::new(&[0xff; 32]);
+    for count in [0, 129, 200] {
+        assert_eq!(reader.read_bits(count, &config).unwrap_err().kind, DeserializationErrorKind::InvalidBitCount(count));
+        assert_eq!(
+            reader.read_signed_bits(count, &config).unwrap_err().kind,
+            DeserializationErrorKind::InvalidBitCount(count)
+        );
+    }
+    assert_eq!(reader.read_bits(128, &config).unwrap(), u128::MAX);
+}
+
+/// Hand-written container of a length prefixed `Manual`
+#[derive(Debug, PartialEq)]
+struct Wrapped(Manual);
+
+impl ToBytes for Wrapped {
+    fn to_bytes_internal(&self, writer: &mut BitWriter, config: &SerializationConfig) -> Result<(), SerializationError> {
+        writer.write_length_prefixed(config, |writer| self.0.to_bytes_internal(writer, config))
+    }
+}
+
+impl FromBytes<'_> for Wrapped {
+    fn from_bytes_internal(reader: &mut BitReader, config: &SerializationConfig) -> Result<Self, DeserializationError> {
+        Ok(Wrapped(reader.read_length_prefixed(config, |reader| Manual::from_bytes_internal(reader, config))?))
+    }
+}
+
+#[test]
+fn test_length_prefixed_hand_written() {
+    let config = SerializationConfig::default();
+    let value = Wrapped(Manual { level: 1, on: false, offset: 2, delta: 3, count: 4, name: "n".into(), id: 5 });
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes[0] as usize, bytes.len() - 1);
+    assert_eq!(Wrapped::from_bytes(&bytes, &config).unwrap(), value);
+
+    // The prefix is one byte short: the id can't be read, and reading on won't help
+    let mut short = bytes.clone();
+    short[0] -= 1;
+    let error = Wrapped::from_bytes(&short, &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::LengthTooShort(bytes.len() - 2));
+}
@@ -0,0 +1,85 @@
+use std::borrow::Cow;
+
+use crate::{DeserializationErrorKind, FromBytes, SerializationConfig, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Frame<'a> {
+    id: u16,
+    #[dynamic_len]
+    name: &'a str,
+    #[dynamic_len(2)]
+    tags: Vec<&'a str>,
+    payload: &'a [u8],
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Record<'a> {
+    length: u8,
+    #[length_determined_by = "length"]
+    key: Cow<'a, [u8]>,
+    #[dynamic_len]
+    value: Cow<'a, str>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Message<'a, T> {
+    Ping,
+    Data {
+        frame: Frame<'a>,
+    },
+    Value(#[dynamic_len] &'a str, T),
+}
+
+#[test]
+fn test_borrowed_fields() {
+    let config = SerializationConfig::default();
+    let frame = Frame { id: 7, name: "cam", tags: vec!["a", "bc"], payload: &[1, 2, 3, 4] };
+    let bytes = frame.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0, 7, 3, b'c', b'a', b'm', 2, 1, b'a', 2, b'b', b'c', 1, 2, 3, 4]);
+
+    let decoded = Frame::from_bytes(&bytes, &config).unwrap();
+    assert_eq!(decoded, frame);
+
+    // The fields point into the input, nothing is copied
+    let range = bytes.as_ptr_range();
+    assert!(range.contains(&decoded.name.as_ptr()));
+    assert!(range.contains(&decoded.tags[1].as_ptr()));
+    assert!(range.contains(&decoded.payload.as_ptr()));
+}
+
+#[test]
+fn test_cow_fields() {
+    let config = SerializationConfig::default();
+    let record = Record { length: 2, key: Cow::Owned(vec![0xab, 0xcd]), value: Cow::Borrowed("on") };
+    let bytes = record.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![2, 0xab, 0xcd, 2, b'o', b'n']);
+
+    let decoded = Record::from_bytes(&bytes, &config).unwrap();
+    assert_eq!(decoded, record);
+    assert!(matches!(decoded.key, Cow::Borrowed(_)));
+    assert!(matches!(decoded.value, Cow::Borrowed(_)));
+}
+
+#[test]
+fn test_borrowed_enum() {
+    let config = SerializationConfig::default();
+    let message = Message::Value("temp", 21u8);
+    let bytes = message.to_bytes(&config).unwrap();
+    assert_eq!(Message::from_bytes(&bytes, &config).unwrap(), message);
+
+    let frame = Frame { id: 1, name: "", tags: vec![], payload: &[9] };
+    let message: Message<u8> = Message::Data { frame };
+    let bytes = message.to_bytes(&config).unwrap();
+    let Ok(Message::Data::<u8> { frame }) = Message::from_bytes(&bytes, &config) else {
+        panic!("Expected Data");
+    };
+    assert_eq!(frame.payload, &[9]);
+}
+
+#[test]
+fn test_borrowed_invalid_utf8() {
+    let config = SerializationConfig::default();
+    let error = Frame::from_bytes(&[0, 1, 1, 0xff, 0], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::InvalidUtf8);
+    assert_eq!(error.to_string(), "invalid UTF-8 in Frame.name at byte 2, bit 0");
+}
@@ -0,0 +1,133 @@
+use crate::{FromBytes, LengthPrefix, SerializationConfig, SerializationErrorKind, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Aligned {
+    #[bits = 3]
+    tag: u8,
+    #[align = 4]
+    value: u16,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Message {
+    id: u8,
+    #[dynamic_len]
+    inner: Aligned,
+    #[dynamic_len]
+    blob: Blob,
+    size: u8,
+    #[length_determined_by = "size"]
+    sized: Aligned,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Blob {
+    data: Vec<u8>,
+}
+
+#[test]
+fn test_to_slice() {
+    let config = SerializationConfig::default();
+    let value = Message {
+        id: 7,
+        inner: Aligned { tag: 5, value: 0x0102 },
+        blob: Blob { data: vec![0, 1, 2] },
+        size: 6,
+        sized: Aligned { tag: 1, value: 2 },
+    };
+    let expected = value.to_bytes(&config).unwrap();
+
+    let mut buf = [0xff; 64];
+    let len = value.to_slice(&mut buf, &config).unwrap();
+    assert_eq!(&buf[..len], expected);
+    assert!(buf[len..].iter().all(|b| *b == 0xff));
+
+    let mut exact = vec![0; expected.len()];
+    assert_eq!(value.to_slice(&mut exact, &config).unwrap(), expected.len());
+    assert_eq!(exact, expected);
+
+    let mut short = vec![0; expected.len() - 1];
+    let err = value.to_slice(&mut short, &config).unwrap_err();
+    assert_eq!(err.kind, SerializationErrorKind::BufferTooSmall(expected.len() - 1));
+    assert_eq!(err.to_string(), format!("buffer of {} bytes is too small for the value in Message.sized.value", expected.len() - 1));
+}
+
+#[test]
+fn test_to_bytes_into() {
+    let config = SerializationConfig::default();
+    let value = Message {
+        id: 7,
+        inner: Aligned { tag: 5, value: 0x0102 },
+        blob: Blob { data: vec![0, 1, 2] },
+        size: 6,
+        sized: Aligned { tag: 1, value: 2 },
+    };
+
+    // Appends after existing bytes, alignment is relative to the start of the value
+    let mut bytes = vec![0xaa, 0xbb];
+    value.to_bytes_into(&mut bytes, &config).unwrap();
+    assert_eq!(bytes[..2], [0xaa, 0xbb]);
+    assert_eq!(bytes[2..], value.to_bytes(&config).unwrap());
+    assert_eq!(Message::from_bytes(&bytes[2..], &config).unwrap(), value);
+}
+
+#[test]
+fn test_back_patched_length_prefix() {
+    // Inside a length prefixed value, alignment is relative to the start of that value
+    let config = SerializationConfig::default();
+    let value = Message {
+        id: 7,
+        inner: Aligned { tag: 5, value: 0x0102 },
+        blob: Blob { data: vec![0, 1, 2] },
+        size: 6,
+        sized: Aligned { tag: 1, value: 2 },
+    };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(
+        bytes,
+        vec![7, 6, 5, 0, 0, 0, 1, 2, 3, 0, 1, 2, 6, 1, 0, 0, 0, 0, 2]
+    );
+    assert_eq!(Message::from_bytes(&bytes, &config).unwrap(), value);
+
+    // A dynamic prefix reserves one byte, longer values are moved to make room
+    for blob_len in [127, 128, 20_000] {
+        let value = Message {
+            id: 7,
+            inner: Aligned { tag: 5, value: 0x0102 },
+            blob: Blob { data: (0..blob_len).map(|i| i as u8).collect() },
+            size: 6,
+            sized: Aligned { tag: 1, value: 2 },
+        };
+        let bytes = value.to_bytes(&config).unwrap();
+        let prefix = crate::dyn_int::encode(blob_len as u128);
+        assert_eq!(bytes[8..8 + prefix.len()], prefix);
+        assert_eq!(bytes[8 + prefix.len()..8 + prefix.len() + blob_len], value.blob.data);
+        assert_eq!(Message::from_bytes(&bytes, &config).unwrap(), value);
+
+        let mut buf = vec![0; bytes.len()];
+        assert_eq!(value.to_slice(&mut buf, &config).unwrap(), bytes.len());
+        assert_eq!(buf, bytes);
+        assert_eq!(
+            value.to_slice(&mut buf[..bytes.len() - 10], &config).unwrap_err().kind,
+            SerializationErrorKind::BufferTooSmall(bytes.len() - 10)
+        );
+    }
+
+    let config = SerializationConfig { length_prefix: LengthPrefix::U16, ..Default::default() };
+    let value = Message {
+        id: 7,
+        inner: Aligned { tag: 5, value: 0x0102 },
+        blob: Blob { data: (0..300).map(|i| i as u8).collect() },
+        size: 6,
+        sized: Aligned { tag: 1, value: 2 },
+    };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes[9..11], [1, 44]);
+    assert_eq!(Message::from_bytes(&bytes, &config).unwrap(), value);
+
+    let config = SerializationConfig { length_prefix: LengthPrefix::U8, ..Default::default() };
+    assert_eq!(
+        value.to_bytes(&config).unwrap_err().kind,
+        SerializationErrorKind::UnsignedOutOfBounds(300, 255)
+    );
+}
@@ -0,0 +1,126 @@
+use crate::{DeserializationErrorKind, FromBytes, SerializationConfig, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Blobs {
+    #[bits = 3]
+    kind: u8,
+    #[dynamic_len]
+    data: Vec<u8>,
+    #[bits = 2]
+    flags: u8,
+    key: [u8; 4],
+    count: u8,
+    #[length_determined_by = "count"]
+    #[dynamic_len(2)]
+    chunks: Vec<Vec<u8>>,
+    rest: Vec<u8>,
+}
+
+/// Same layout as `Blobs`, but `#[bits = 8]` writes every byte separately
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct BitBlobs {
+    #[bits = 3]
+    kind: u8,
+    #[dynamic_len]
+    #[bits = 8]
+    data: Vec<u8>,
+    #[bits = 2]
+    flags: u8,
+    #[bits = 8]
+    key: [u8; 4],
+    count: u8,
+    #[length_determined_by = "count"]
+    #[dynamic_len(2)]
+    #[bits = 8]
+    chunks: Vec<Vec<u8>>,
+    #[bits = 8]
+    rest: Vec<u8>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Empty {
+    #[bits = 2]
+    a: u8,
+    #[bits = 3]
+    len: u8,
+    #[length_determined_by = "len"]
+    data: Vec<u8>,
+    #[bits = 3]
+    b: u8,
+}
+
+#[test]
+fn test_byte_blobs_match_per_element_encoding() {
+    let config = SerializationConfig::default();
+    let blobs = Blobs {
+        kind: 5,
+        data: (0..=255).collect(),
+        flags: 2,
+        key: [1, 2, 3, 4],
+        count: 2,
+        chunks: vec![vec![7; 3], vec![]],
+        rest: vec![9, 8],
+    };
+    let bit_blobs = BitBlobs {
+        kind: 5,
+        data: (0..=255).collect(),
+        flags: 2,
+        key: [1, 2, 3, 4],
+        count: 2,
+        chunks: vec![vec![7; 3], vec![]],
+        rest: vec![9, 8],
+    };
+
+    let bytes = blobs.to_bytes(&config).unwrap();
+    assert_eq!(bytes, bit_blobs.to_bytes(&config).unwrap());
+    assert_eq!(Blobs::from_bytes(&bytes, &config).unwrap(), blobs);
+    assert_eq!(BitBlobs::from_bytes(&bytes, &config).unwrap(), bit_blobs);
+}
+
+#[test]
+fn test_empty_byte_blob_keeps_bit_position() {
+    let config = SerializationConfig::default();
+    let value = Empty { a: 1, len: 0, data: vec![], b: 2 };
+    let bytes = value.to_bytes(&config).unwrap();
+    // a (bits 0-1), len (bits 2-4) and b (bits 5-7) share the byte around the empty vec
+    assert_eq!(bytes, vec![0b0100_0001]);
+    assert_eq!(Empty::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[test]
+fn test_byte_blob_not_enough_bytes() {
+    let config = SerializationConfig::default();
+    let bytes = Blobs {
+        kind: 0,
+        data: vec![1; 10],
+        flags: 0,
+        key: [0; 4],
+        count: 0,
+        chunks: vec![],
+        rest: vec![],
+    }
+    .to_bytes(&config)
+    .unwrap();
+
+    let error = Blobs::from_bytes(&bytes[..8], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::NotEnoughBytes(Some(4)));
+    assert_eq!(error.to_string(), "not enough bytes (4 missing) in Blobs.data at byte 0, bit 3");
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn test_bytes_buffer() {
+    #[derive(ToBytes, FromBytes, Debug, PartialEq)]
+    struct Packet {
+        id: u8,
+        #[dynamic_len]
+        header: bytes::Bytes,
+        body: bytes::Bytes,
+    }
+
+    let config = SerializationConfig::default();
+    let packet = Packet { id: 1, header: bytes::Bytes::from_static(b"hd"), body: bytes::Bytes::from(vec![0xaa; 3]) };
+    let bytes = packet.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![1, 2, b'h', b'd', 0xaa, 0xaa, 0xaa]);
+    assert_eq!(Packet::from_bytes(&bytes, &config).unwrap(), packet);
+}
@@ -0,0 +1,177 @@
+use bytes::BytesMut;
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::AsyncWriteExt;
+use tokio_util::codec::{Decoder, Encoder, Framed, FramedRead};
+
+use crate::{
+    BinaryCodec, DeserializationErrorKind, FromBytes, SerializationConfig, SerializationErrorKind, StreamError, ToBytes,
+    DEFAULT_MAX_FRAME_LENGTH,
+};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq, Clone)]
+struct Request {
+    id: u32,
+    #[dynamic_len]
+    path: String,
+    #[dynamic_len]
+    body: Vec<u8>,
+}
+
+/// Not self-delimiting: the body is the rest of the input
+#[derive(ToBytes, FromBytes, Debug, PartialEq, Clone)]
+struct Datagram {
+    port: u16,
+    body: Vec<u8>,
+}
+
+#[tokio::test]
+async fn test_codec_over_duplex() {
+    let config = SerializationConfig::default();
+    let (client, server) = tokio::io::duplex(16);
+    let requests: Vec<_> =
+        (0..20).map(|id| Request { id, path: format!("/items/{}", id), body: vec![id as u8; id as usize * 10] }).collect();
+
+    let sent = requests.clone();
+    let sender = tokio::spawn(async move {
+        let mut framed = Framed::new(client, BinaryCodec::<Request>::new(config));
+        for request in sent {
+            framed.send(request).await.unwrap();
+        }
+    });
+
+    let received: Vec<Request> = FramedRead::new(server, BinaryCodec::new(config))
+        .map(|request| request.unwrap())
+        .collect()
+        .await;
+    sender.await.unwrap();
+    assert_eq!(received, requests);
+}
+
+#[tokio::test]
+async fn test_length_delimited_codec_over_duplex() {
+    let config = SerializationConfig::default();
+    let (client, server) = tokio::io::duplex(64);
+    let datagrams: Vec<_> = (0..5).map(|port| Datagram { port, body: vec![7; port as usize] }).collect();
+
+    let sent = datagrams.clone();
+    let sender = tokio::spawn(async move {
+        let mut framed = Framed::new(client, BinaryCodec::<Datagram>::length_delimited(config));
+        for datagram in &sent {
+            framed.send(datagram).await.unwrap();
+        }
+    });
+
+    let received: Vec<Datagram> = FramedRead::new(server, BinaryCodec::length_delimited(config))
+        .map(|datagram| datagram.unwrap())
+        .collect()
+        .await;
+    sender.await.unwrap();
+    assert_eq!(received, datagrams);
+}
+
+#[test]
+fn test_decoder_waits_for_partial_frame() {
+    let config = SerializationConfig::default();
+    let mut codec = BinaryCodec::<Request>::new(config);
+    let request = Request { id: 3, path: "/items/3".into(), body: vec![3; 30] };
+    let mut encoded = BytesMut::new();
+    codec.encode(&request, &mut encoded).unwrap();
+
+    let mut src = BytesMut::new();
+    for byte in encoded.iter() {
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        src.extend_from_slice(&[*byte]);
+    }
+    assert_eq!(codec.decode(&mut src).unwrap(), Some(request));
+    assert!(src.is_empty());
+}
+
+#[test]
+fn test_decoder_rejects_type_without_end() {
+    // Without a frame length, the bytes buffered so far are not the whole body
+    let config = SerializationConfig::default();
+    let mut codec = BinaryCodec::<Datagram>::new(config);
+    let mut src = BytesMut::from(&[0x1f, 0x90, 1, 2][..]);
+    let Err(StreamError::Deserialization(error)) = codec.decode(&mut src) else {
+        panic!("Expected deserialization error");
+    };
+    assert_eq!(error.kind, DeserializationErrorKind::NotSelfDelimiting);
+    assert_eq!(src.len(), 4);
+
+    let datagram = Datagram { port: 8080, body: vec![1, 2] };
+    let mut codec = BinaryCodec::<Datagram>::length_delimited(config);
+    let mut src = BytesMut::new();
+    codec.encode(&datagram, &mut src).unwrap();
+    assert_eq!(codec.decode(&mut src).unwrap(), Some(datagram));
+}
+
+#[tokio::test]
+async fn test_codec_invalid_frame() {
+    let config = SerializationConfig::default();
+    let (mut client, server) = tokio::io::duplex(64);
+    // The frame is 2 bytes long, but the value needs at least the port and no body
+    client.write_all(&[1, 0xab]).await.unwrap();
+    drop(client);
+
+    let mut framed = FramedRead::new(server, BinaryCodec::<Datagram>::length_delimited(config));
+    let Some(Err(StreamError::Deserialization(error))) = framed.next().await else {
+        panic!("Expected deserialization error");
+    };
+    assert_eq!(error.kind, DeserializationErrorKind::LengthTooShort(1));
+}
+
+#[tokio::test]
+async fn test_codec_stream_ends_in_frame() {
+    let config = SerializationConfig::default();
+    let (mut client, server) = tokio::io::duplex(64);
+    let request = Request { id: 2, path: "/items/2".into(), body: vec![2; 20] };
+    let bytes = request.to_bytes(&config).unwrap();
+    client.write_all(&bytes[..bytes.len() - 1]).await.unwrap();
+    drop(client);
+
+    let mut framed = FramedRead::new(server, BinaryCodec::<Request>::new(config));
+    assert!(matches!(framed.next().await, Some(Err(StreamError::Io(_)))));
+}
+
+#[test]
+fn test_decoder_rejects_oversized_frame() {
+    let config = SerializationConfig::default();
+    // Claims a length of about 2^63 bytes in 10 bytes
+    let oversized = [1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+
+    let mut codec = BinaryCodec::<Datagram>::length_delimited(config);
+    let Err(StreamError::Deserialization(error)) = codec.decode(&mut BytesMut::from(&oversized[1..])) else {
+        panic!("Expected deserialization error");
+    };
+    assert!(matches!(error.kind, DeserializationErrorKind::LengthLimitExceeded(_, DEFAULT_MAX_FRAME_LENGTH)));
+
+    let mut codec = BinaryCodec::<Request>::new(config);
+    let mut src = BytesMut::from(&[0, 0, 0, 1][..]);
+    src.extend_from_slice(&oversized[1..]);
+    let Err(StreamError::Deserialization(error)) = codec.decode(&mut src) else {
+        panic!("Expected deserialization error");
+    };
+    assert!(matches!(error.kind, DeserializationErrorKind::LengthLimitExceeded(_, DEFAULT_MAX_FRAME_LENGTH)));
+
+    // The configured limit on lengths applies to the prefix itself
+    let mut codec = BinaryCodec::<Datagram>::length_delimited(SerializationConfig { max_length: 100, ..config });
+    let Err(StreamError::Deserialization(error)) = codec.decode(&mut BytesMut::from(&[101][..])) else {
+        panic!("Expected deserialization error");
+    };
+    assert_eq!(error.kind, DeserializationErrorKind::LengthLimitExceeded(101, 100));
+}
+
+#[test]
+fn test_max_frame_length() {
+    let config = SerializationConfig::default();
+    let mut codec = BinaryCodec::<Datagram>::length_delimited(config).with_max_frame_length(10);
+    let mut dst = BytesMut::new();
+    codec.encode(Datagram { port: 1, body: vec![0; 7] }, &mut dst).unwrap();
+    let err = codec.encode(Datagram { port: 1, body: vec![0; 9] }, &mut dst).unwrap_err();
+    assert!(matches!(err, StreamError::Serialization(e) if e.kind == SerializationErrorKind::LengthLimitExceeded(12, 10)));
+
+    // A frame of 10 bytes (prefix included) is accepted, one more byte is rejected before it arrives
+    assert_eq!(codec.decode(&mut dst).unwrap(), Some(Datagram { port: 1, body: vec![0; 7] }));
+    let mut src = BytesMut::from(&[10, 0][..]);
+    assert!(matches!(codec.decode(&mut src), Err(StreamError::Deserialization(_))));
+}
@@ -0,0 +1,105 @@
+use crate::{
+    ByteOrder, DeserializationErrorKind, FromBytes, LengthPrefix, SerializationConfig, ToBytes,
+};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Message {
+    id: u16,
+    #[dynamic_len]
+    name: String,
+    #[dynamic_len]
+    values: Vec<u32>,
+}
+
+#[test]
+fn test_default_profile() {
+    let config = SerializationConfig::default();
+    let value = Message { id: 0x0102, name: "ab".to_string(), values: vec![3] };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![1, 2, 2, b'a', b'b', 1, 0, 0, 0, 3]);
+    assert_eq!(Message::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[test]
+fn test_little_endian_u16_prefix_profile() {
+    let config = SerializationConfig {
+        byte_order: ByteOrder::LittleEndian,
+        length_prefix: LengthPrefix::U16,
+        ..Default::default()
+    };
+    let value = Message { id: 0x0102, name: "ab".to_string(), values: vec![3] };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![2, 1, 2, 0, b'a', b'b', 1, 0, 3, 0, 0, 0]);
+    assert_eq!(Message::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[test]
+fn test_empty_values_roundtrip() {
+    let config = SerializationConfig::default();
+    let value = Message { id: 1, name: String::new(), values: vec![] };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0, 1, 0, 0]);
+    assert_eq!(Message::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[test]
+fn test_max_length_limit() {
+    let config = SerializationConfig { max_length: 1, ..Default::default() };
+    let value = Message { id: 0x0102, name: "ab".to_string(), values: vec![3] };
+    assert!(value.to_bytes(&config).is_err());
+
+    let bytes = value.to_bytes(&SerializationConfig::default()).unwrap();
+    assert_eq!(
+        Message::from_bytes(&bytes, &config).unwrap_err().kind,
+        DeserializationErrorKind::LengthLimitExceeded(2, 1)
+    );
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct MixedEndian {
+    a: u16,
+    #[little_endian]
+    b: u16,
+    #[big_endian]
+    c: u32,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[little_endian]
+struct LittleEndianHeader {
+    a: u16,
+    #[big_endian]
+    b: u16,
+    inner: MixedEndian,
+}
+
+#[test]
+fn test_field_endianness() {
+    let value = MixedEndian { a: 0x0102, b: 0x0304, c: 0x05060708 };
+    for config in [
+        SerializationConfig::default(),
+        SerializationConfig::default().with_byte_order(ByteOrder::LittleEndian),
+    ] {
+        let bytes = value.to_bytes(&config).unwrap();
+        let a = match config.byte_order {
+            ByteOrder::BigEndian => [1, 2],
+            ByteOrder::LittleEndian => [2, 1],
+        };
+        assert_eq!(bytes, [&a[..], &[4, 3, 5, 6, 7, 8]].concat());
+        assert_eq!(MixedEndian::from_bytes(&bytes, &config).unwrap(), value);
+    }
+}
+
+#[test]
+fn test_container_endianness() {
+    let config = SerializationConfig::default();
+    let value = LittleEndianHeader {
+        a: 0x0102,
+        b: 0x0304,
+        inner: MixedEndian { a: 0x0506, b: 0x0708, c: 0x090a0b0c },
+    };
+    let bytes = value.to_bytes(&config).unwrap();
+    // Nested types without their own attribute inherit the container byte order
+    assert_eq!(bytes, vec![2, 1, 3, 4, 6, 5, 8, 7, 9, 10, 11, 12]);
+    assert_eq!(LittleEndianHeader::from_bytes(&bytes, &config).unwrap(), value);
+}
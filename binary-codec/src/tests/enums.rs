@@ -0,0 +1,192 @@
+use super::roundtrip;
+use crate::{FromBytes, SerializationConfig, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Command {
+    Reset,
+    SetLevel(#[bits = 3] u8, #[bits = 5] i8),
+    Write {
+        #[dynamic]
+        address: u32,
+        #[dynamic_len]
+        data: Vec<u8>,
+    },
+    Fill {
+        count: u8,
+        has_pattern: bool,
+        #[length_determined_by = "count"]
+        values: Vec<u8>,
+        #[toggled_by = "has_pattern"]
+        pattern: Option<u16>,
+    },
+    Tagged(u8, #[length_determined_by = "0"] String),
+}
+
+#[test]
+fn test_bit_packed_variant_fields() {
+    assert_eq!(roundtrip(&Command::Reset), vec![0]);
+    assert_eq!(roundtrip(&Command::SetLevel(5, -3)), vec![1, 0b0010_1101]);
+    assert!(Command::SetLevel(8, 0).to_bytes(&SerializationConfig::default()).is_err());
+}
+
+#[test]
+fn test_length_prefixed_variant_fields() {
+    assert_eq!(
+        roundtrip(&Command::Write { address: 300, data: vec![1, 2] }),
+        vec![2, 0b1010_1100, 0b0000_0010, 2, 1, 2]
+    );
+}
+
+#[test]
+fn test_sibling_references_in_variant() {
+    assert_eq!(
+        roundtrip(&Command::Fill { count: 2, has_pattern: true, values: vec![4, 5], pattern: Some(0x0607) }),
+        vec![3, 2, 1, 4, 5, 6, 7]
+    );
+    assert_eq!(
+        roundtrip(&Command::Fill { count: 0, has_pattern: false, values: vec![], pattern: None }),
+        vec![3, 0, 0]
+    );
+    assert_eq!(roundtrip(&Command::Tagged(2, "hi".to_string())), vec![4, 2, b'h', b'i']);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Opcode {
+    Nop = 0x10,
+    Load,
+    Store = 0x20,
+    Halt = 0xFF,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Frame {
+    #[disc = 7]
+    Data(u8),
+    Ack,
+    #[disc = 2]
+    Error { code: u8 },
+}
+
+#[test]
+fn test_explicit_discriminants() {
+    let config = SerializationConfig::default();
+    assert_eq!(Opcode::Nop.to_bytes(&config).unwrap(), vec![0x10]);
+    assert_eq!(Opcode::Load.to_bytes(&config).unwrap(), vec![0x11]);
+    assert_eq!(Opcode::Store.to_bytes(&config).unwrap(), vec![0x20]);
+    assert_eq!(Opcode::from_bytes(&[0xFF], &config).unwrap(), Opcode::Halt);
+    assert!(Opcode::from_bytes(&[0], &config).is_err());
+}
+
+#[test]
+fn test_disc_attribute() {
+    let config = SerializationConfig::default();
+    assert_eq!(Frame::Data(1).to_bytes(&config).unwrap(), vec![7, 1]);
+    assert_eq!(Frame::Ack.to_bytes(&config).unwrap(), vec![8]);
+    assert_eq!(Frame::from_bytes(&[2, 9], &config).unwrap(), Frame::Error { code: 9 });
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[disc_bits = 3]
+enum Priority {
+    Low,
+    High = 7,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Packed {
+    priority: Priority,
+    #[bits = 5]
+    channel: u8,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[disc_type = "u16"]
+enum Wide {
+    #[disc = 0x1234]
+    A,
+    B(u8),
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[disc_type = "u32"]
+#[disc_dynamic]
+enum Varint {
+    Small = 1,
+    Large = 300,
+}
+
+#[test]
+fn test_bit_packed_discriminant() {
+    let config = SerializationConfig::default();
+    let packed = Packed { priority: Priority::High, channel: 3 };
+    let bytes = packed.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0b0001_1111]);
+    assert_eq!(Packed::from_bytes(&bytes, &config).unwrap(), packed);
+}
+
+#[test]
+fn test_wide_discriminant() {
+    let config = SerializationConfig::default();
+    assert_eq!(Wide::A.to_bytes(&config).unwrap(), vec![0x12, 0x34]);
+    assert_eq!(Wide::B(1).to_bytes(&config).unwrap(), vec![0x12, 0x35, 1]);
+    assert_eq!(Wide::from_bytes(&[0x12, 0x35, 1], &config).unwrap(), Wide::B(1));
+    assert_eq!(
+        Wide::from_bytes(&[0x01, 0x00], &config).unwrap_err().kind,
+        crate::DeserializationErrorKind::UnknownDiscriminant(0x100)
+    );
+}
+
+#[test]
+fn test_dynamic_discriminant() {
+    let config = SerializationConfig::default();
+    assert_eq!(Varint::Small.to_bytes(&config).unwrap(), vec![1]);
+    assert_eq!(Varint::Large.to_bytes(&config).unwrap(), vec![0b1010_1100, 0b0000_0010]);
+    assert_eq!(Varint::from_bytes(&[0b1010_1100, 0b0000_0010], &config).unwrap(), Varint::Large);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Message {
+    #[disc = 1]
+    Ping,
+    Text(#[dynamic_len] String),
+    #[fallback]
+    Unknown(u8, Vec<u8>),
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Envelope {
+    #[dynamic_len]
+    message: MessageDelimited,
+    checksum: u8,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum MessageDelimited {
+    Ping,
+    #[fallback]
+    Unknown {
+        disc: u8,
+        #[dynamic_len]
+        data: Vec<u8>,
+    },
+}
+
+#[test]
+fn test_fallback_variant() {
+    let config = SerializationConfig::default();
+    assert_eq!(Message::from_bytes(&[2, 1, b'a'], &config).unwrap(), Message::Text("a".to_string()));
+
+    let unknown = Message::from_bytes(&[9, 1, 2, 3], &config).unwrap();
+    assert_eq!(unknown, Message::Unknown(9, vec![1, 2, 3]));
+    assert_eq!(unknown.to_bytes(&config).unwrap(), vec![9, 1, 2, 3]);
+}
+
+#[test]
+fn test_length_delimited_fallback_variant() {
+    let config = SerializationConfig::default();
+    let bytes = vec![4, 5, 2, 7, 8, 0xAA];
+    let envelope = Envelope::from_bytes(&bytes, &config).unwrap();
+    assert_eq!(envelope.message, MessageDelimited::Unknown { disc: 5, data: vec![7, 8] });
+    assert_eq!(envelope.checksum, 0xAA);
+    assert_eq!(envelope.to_bytes(&config).unwrap(), bytes);
+}
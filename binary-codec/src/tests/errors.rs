@@ -0,0 +1,224 @@
+use crate::{
+    DeserializationErrorKind, FromBytes, PathSegment, SerializationConfig, SerializationError, SerializationErrorKind,
+    ToBytes,
+};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Header {
+    #[bits = 4]
+    version: u8,
+    #[bits = 4]
+    kind: Kind,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[disc_bits = 4]
+enum Kind {
+    Ping,
+    Data,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Item {
+    header: Header,
+    id: u16,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Frame {
+    seq: u8,
+    #[dynamic_len]
+    payload: Vec<Item>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Message {
+    Text(#[dynamic_len] String),
+    Framed {
+        #[dynamic_len]
+        frame: Frame,
+    },
+    Samples(#[dynamic_len] Vec<Sample>),
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Sample {
+    #[bits = 40]
+    timestamp: u64,
+    #[bits = 8]
+    value: u8,
+}
+
+#[test]
+fn test_error_path_and_offset() {
+    let config = SerializationConfig::default();
+    let frame = Frame {
+        seq: 1,
+        payload: (0..4).map(|id| Item { header: Header { version: 1, kind: Kind::Data }, id }).collect(),
+    };
+    let mut bytes = frame.to_bytes(&config).unwrap();
+    // seq (1) + length (1) + 3 items of 3 bytes, the kind is the high nibble of the first byte of payload[3]
+    bytes[11] = 0x71;
+
+    let error = Frame::from_bytes(&bytes, &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::UnknownDiscriminant(7));
+    assert_eq!(error.type_name, Some("Frame"));
+    assert_eq!(
+        error.path,
+        vec![
+            PathSegment::Field("payload"),
+            PathSegment::Index(3),
+            PathSegment::Field("header"),
+            PathSegment::Field("kind"),
+        ]
+    );
+    assert_eq!(error.offset, Some((11, 4)));
+    assert_eq!(
+        error.to_string(),
+        "unknown discriminant 7 in Frame.payload[3].header.kind at byte 11, bit 4"
+    );
+}
+
+#[test]
+fn test_error_not_enough_bytes() {
+    let config = SerializationConfig::default();
+    let frame = Frame {
+        seq: 1,
+        payload: (0..4).map(|id| Item { header: Header { version: 1, kind: Kind::Data }, id }).collect(),
+    };
+    let bytes = frame.to_bytes(&config).unwrap();
+
+    let error = Frame::from_bytes(&bytes[..bytes.len() - 1], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::NotEnoughBytes(Some(1)));
+    assert_eq!(error.to_string(), "not enough bytes (1 missing) in Frame.payload[3].id at byte 12, bit 0");
+}
+
+#[test]
+fn test_error_in_variant() {
+    let config = SerializationConfig::default();
+    let error = Message::from_bytes(&[0, 2, b'o', 0xff], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::InvalidUtf8);
+    assert_eq!(error.to_string(), "invalid UTF-8 in Message::Text.0 at byte 1, bit 0");
+
+    // Errors of the outermost container are at its start
+    let error = Message::from_bytes(&[5], &config).unwrap_err();
+    assert_eq!(error.offset, Some((0, 0)));
+    assert_eq!(error.to_string(), "unknown discriminant 5 in Message at byte 0, bit 0");
+}
+
+#[test]
+fn test_error_offset_in_length_delimited_object() {
+    let config = SerializationConfig::default();
+    let frame = Frame {
+        seq: 1,
+        payload: (0..4).map(|id| Item { header: Header { version: 1, kind: Kind::Data }, id }).collect(),
+    };
+    let mut bytes = Message::Framed { frame }.to_bytes(&config).unwrap();
+    // Variant and length prefix of the frame come before it
+    bytes[2 + 5] = 0xf1;
+
+    let error = Message::from_bytes(&bytes, &config).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unknown discriminant 15 in Message::Framed.frame.payload[1].header.kind at byte 7, bit 4"
+    );
+}
+
+#[test]
+fn test_error_trait() {
+    let config = SerializationConfig::default();
+    let error: Box<dyn std::error::Error> = Box::new(Frame::from_bytes(&[], &config).unwrap_err());
+    assert_eq!(error.to_string(), "not enough bytes (1 missing) in Frame.seq at byte 0, bit 0");
+}
+
+#[test]
+fn test_serialization_error_context() {
+    let config = SerializationConfig::default();
+    let message = Message::Samples(vec![
+        Sample { timestamp: 1, value: 0 },
+        Sample { timestamp: u64::MAX, value: 0 },
+    ]);
+
+    let error = message.to_bytes(&config).unwrap_err();
+    // The value isn't truncated to 32 bits
+    assert_eq!(error.kind, SerializationErrorKind::UnsignedOutOfBounds(u64::MAX as u128, (1 << 40) - 1));
+    assert_eq!(error.type_name, Some("Message"));
+    assert_eq!(
+        error.path,
+        vec![
+            PathSegment::Variant("Samples"),
+            PathSegment::Field("0"),
+            PathSegment::Index(1),
+            PathSegment::Field("timestamp"),
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        "value 18446744073709551615 is out of bounds (0..=1099511627775) in Message::Samples.0[1].timestamp"
+    );
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Toggled {
+    flag: bool,
+    #[toggled_by = "flag"]
+    value: Option<u8>,
+}
+
+#[test]
+fn test_toggle_mismatch() {
+    let config = SerializationConfig::default();
+    let error = Toggled { flag: true, value: None }.to_bytes(&config).unwrap_err();
+    assert_eq!(error.kind, SerializationErrorKind::ToggleMismatch(true));
+    assert_eq!(error.to_string(), "toggled_by field is true, but the value is None in Toggled.value");
+
+    let error = Toggled { flag: false, value: Some(1) }.to_bytes(&config).unwrap_err();
+    assert_eq!(error.kind, SerializationErrorKind::ToggleMismatch(false));
+    assert_eq!(Toggled { flag: false, value: None }.to_bytes(&config).unwrap(), vec![0]);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Indexed {
+    #[dynamic_len]
+    lens: Vec<u8>,
+    #[length_determined_by = "lens.2"]
+    data: Vec<u8>,
+    #[dynamic_len]
+    flags: Vec<bool>,
+    #[toggled_by = "flags.1"]
+    value: Option<u8>,
+}
+
+#[test]
+fn test_reference_out_of_bounds() {
+    let config = SerializationConfig::default();
+    let value = Indexed { lens: vec![0, 0, 2], data: vec![7, 8], flags: vec![false, true], value: Some(9) };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(Indexed::from_bytes(&bytes, &config).unwrap(), value);
+
+    // The input has fewer lengths or flags than the references need
+    let error = Indexed::from_bytes(&[1, 0], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::ReferenceOutOfBounds(2, 1));
+    assert_eq!(error.to_string(), "referenced index 2 is out of bounds for 1 elements in Indexed.data at byte 2, bit 0");
+    let error = Indexed::from_bytes(&[3, 0, 0, 0, 1, 0], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::ReferenceOutOfBounds(1, 1));
+
+    let value = Indexed { lens: vec![], data: vec![], flags: vec![], value: None };
+    let error = value.to_bytes(&config).unwrap_err();
+    assert_eq!(error.kind, SerializationErrorKind::ReferenceOutOfBounds(2, 0));
+}
+
+#[test]
+fn test_errors_compose() {
+    fn roundtrip(message: &Message) -> Result<Message, Box<dyn std::error::Error + Send + Sync>> {
+        let config = SerializationConfig::default();
+        let bytes = message.to_bytes(&config)?;
+        Ok(Message::from_bytes(&bytes, &config)?)
+    }
+
+    let message = Message::Text("hello".into());
+    assert_eq!(roundtrip(&message).unwrap(), message);
+
+    let error = roundtrip(&Message::Samples(vec![Sample { timestamp: 1 << 40, value: 0 }])).unwrap_err();
+    assert!(error.downcast_ref::<SerializationError>().is_some());
+}
@@ -0,0 +1,63 @@
+use crate::{ByteOrder, FromBytes, SerializationConfig, SerializationErrorKind, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Telemetry {
+    temperature: f32,
+    altitude: f64,
+    #[little_endian]
+    pressure: f32,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct PackedTelemetry {
+    #[bits = 3]
+    mode: u8,
+    #[fixed_point(scale = 100, bits = 12)]
+    temperature: f32,
+    #[fixed_point(scale = 10, bits = 9, unsigned)]
+    humidity: f64,
+    #[f16]
+    speed: f32,
+    #[f16]
+    samples: Vec<f32>,
+}
+
+#[test]
+fn test_float_fields() {
+    let config = SerializationConfig::default();
+    let value = Telemetry { temperature: 21.5, altitude: -1234.125, pressure: 1013.25 };
+    let bytes = value.to_bytes(&config).unwrap();
+    let expected = [
+        &21.5f32.to_be_bytes()[..],
+        &(-1234.125f64).to_be_bytes()[..],
+        &1013.25f32.to_le_bytes()[..],
+    ]
+    .concat();
+    assert_eq!(bytes, expected);
+    assert_eq!(Telemetry::from_bytes(&bytes, &config).unwrap(), value);
+
+    let config = config.with_byte_order(ByteOrder::LittleEndian);
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes[..4], 21.5f32.to_le_bytes());
+    assert_eq!(Telemetry::from_bytes(&bytes, &config).unwrap(), value);
+}
+
+#[test]
+fn test_reduced_precision_floats() {
+    let config = SerializationConfig::default();
+    let value = PackedTelemetry {
+        mode: 5,
+        temperature: -12.5,
+        humidity: 45.3,
+        speed: 0.5,
+        samples: vec![1.0, -2.0],
+    };
+    let bytes = value.to_bytes(&config).unwrap();
+
+    // 3 + 12 + 9 + 16 bits for the header fields, then 2 half floats
+    assert_eq!(bytes.len(), 5 + 4);
+    assert_eq!(PackedTelemetry::from_bytes(&bytes, &config).unwrap(), value);
+
+    let value = PackedTelemetry { temperature: 25.0, ..value };
+    assert_eq!(value.to_bytes(&config).unwrap_err().kind, SerializationErrorKind::ValueOutOfBounds(2500, -2048, 2047));
+}
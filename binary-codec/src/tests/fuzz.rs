@@ -0,0 +1,129 @@
+//! Deterministic fuzzing of the read paths: decoding arbitrary or corrupted bytes
+//! should return an error, never panic.
+
+use std::collections::HashMap;
+
+use crate::fuzzing::{check, Everything, Inner, Kind, Packed};
+use crate::{BitOrder, BitPacking, ByteOrder, FromBytes, LengthPrefix, SerializationConfig, ToBytes};
+
+/// Small xorshift generator, so failures are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+fn configs() -> Vec<SerializationConfig> {
+    let default = SerializationConfig::default();
+    vec![
+        default,
+        SerializationConfig { length_prefix: LengthPrefix::U8, ..default },
+        SerializationConfig {
+            length_prefix: LengthPrefix::U32,
+            byte_order: ByteOrder::LittleEndian,
+            ..default
+        },
+        SerializationConfig { max_length: 3, ..default },
+        default.with_bit_packing(BitPacking::Contiguous).with_bit_order(BitOrder::Msb),
+    ]
+}
+
+fn samples() -> Vec<Vec<u8>> {
+    let config = SerializationConfig::default();
+    let everything = Everything {
+        reserved: (),
+        flag: true,
+        count: 2,
+        values: vec![1, 2],
+        maybe: Some(-5),
+        name: "fuzz".to_string(),
+        inner: Inner { a: 3, b: -100, c: 1 << 40 },
+        kinds: [Kind::B("x".to_string()), Kind::C { x: 1.5, y: 2.0 }],
+        map: HashMap::from([(1, vec![true, false])]),
+        temperature: -20.5,
+        end: 0,
+        nested: vec![vec![1, 2, 3], vec![]],
+        lens: vec![0, 2],
+        sized: vec![5, 6],
+        flags: vec![false, true, true],
+        indexed: Some(4),
+        rest: "tail".to_string(),
+    };
+    let packed = Packed {
+        a: 5,
+        b: 123_456,
+        c: true,
+        d: 7,
+        tail: vec![Inner { a: 1, b: 2, c: 3 }],
+    };
+
+    vec![everything.to_bytes(&config).unwrap(), packed.to_bytes(&config).unwrap()]
+}
+
+#[test]
+fn test_fuzz_random_bytes() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..5_000 {
+        let len = rng.below(48);
+        let mut bytes = rng.bytes(len);
+        // Often start with the magic, to get past it
+        if rng.below(2) == 0 && bytes.len() >= 2 {
+            bytes[..2].copy_from_slice(b"FZ");
+        }
+
+        for config in configs() {
+            check::<Everything>(&bytes, &config);
+            check::<Packed>(&bytes, &config);
+            check::<Kind>(&bytes, &config);
+            check::<Inner>(&bytes, &config);
+        }
+    }
+}
+
+#[test]
+fn test_fuzz_corrupted_samples() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let samples = samples();
+    let config = SerializationConfig::default();
+    assert!(Everything::from_bytes(&samples[0], &config).is_ok());
+    assert!(Packed::from_bytes(&samples[1], &config).is_ok());
+
+    for _ in 0..5_000 {
+        let mut bytes = samples[rng.below(samples.len())].clone();
+
+        match rng.below(3) {
+            // Flip a few bytes
+            0 => {
+                for _ in 0..=rng.below(4) {
+                    let i = rng.below(bytes.len());
+                    bytes[i] = rng.next() as u8;
+                }
+            }
+            // Truncate
+            1 => bytes.truncate(rng.below(bytes.len())),
+            // Huge dynamic length somewhere
+            _ => {
+                let i = rng.below(bytes.len());
+                bytes.splice(i..i, [0xff, 0xff, 0xff, 0xff, 0x0f]);
+            }
+        }
+
+        for config in configs() {
+            check::<Everything>(&bytes, &config);
+            check::<Packed>(&bytes, &config);
+        }
+    }
+}
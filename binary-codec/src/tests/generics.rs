@@ -0,0 +1,115 @@
+use super::roundtrip;
+use crate::{FromBytes, FromBytesOwned, SerializationConfig, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Header {
+    #[bits = 4]
+    kind: u8,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Packet<T> {
+    header: Header,
+    body: T,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Batch<T> {
+    #[dynamic_len]
+    items: Vec<T>,
+    last: Option<T>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Either<L, R> {
+    Left(L),
+    Right { value: R },
+}
+
+trait Payload: ToBytes + FromBytesOwned {}
+impl Payload for u16 {}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[bound = "T: Payload"]
+struct Envelope<T: Payload> {
+    id: u8,
+    payload: T,
+}
+
+#[test]
+fn test_generic_struct() {
+    let packet = Packet { header: Header { kind: 3 }, body: Header { kind: 5 } };
+    assert_eq!(roundtrip(&packet), vec![0x53]); // both 4 bit fields share a byte
+
+    let config = SerializationConfig::default();
+    let bytes = vec![1, 0, 0, 0, 9];
+    let packet = Packet::<u32>::from_bytes(&bytes, &config).unwrap();
+    assert_eq!(packet.body, 9);
+}
+
+#[test]
+fn test_generic_containers() {
+    let batch = Batch { items: vec![Header { kind: 1 }, Header { kind: 2 }], last: Some(Header { kind: 3 }) };
+    assert_eq!(roundtrip(&batch), vec![2, 0x21, 3]);
+}
+
+#[test]
+fn test_generic_enum() {
+    let left: Either<u8, u16> = Either::Left(7);
+    assert_eq!(roundtrip(&left), vec![0, 7]);
+
+    let right: Either<u8, u16> = Either::Right { value: 0x0102 };
+    assert_eq!(roundtrip(&right), vec![1, 1, 2]);
+}
+
+#[test]
+fn test_bound_override() {
+    let envelope = Envelope { id: 1, payload: 0x0203u16 };
+    assert_eq!(roundtrip(&envelope), vec![1, 2, 3]);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Framed<T> {
+    #[bits = 3]
+    kind: u8,
+    #[dynamic_len]
+    body: T,
+    trailer: u8,
+}
+
+#[test]
+fn test_length_delimited_generic_field() {
+    let framed = Framed { kind: 5, body: Header { kind: 9 }, trailer: 1 };
+    assert_eq!(roundtrip(&framed), vec![5, 1, 9, 1]);
+}
+
+/// Only owned values are decoded, whatever the lifetime of the input
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[bound(to_bytes = "T: ToBytes", from_bytes = "T: FromBytesOwned")]
+struct Owned<T> {
+    #[dynamic_len]
+    items: Vec<T>,
+}
+
+trait Field<'de>: ToBytes + FromBytes<'de> {}
+impl Field<'_> for u8 {}
+
+/// The decoding bound names the lifetime of the input, `'_p_de`
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[bound(to_bytes = "T: ToBytes", from_bytes = "T: Field<'_p_de>")]
+struct Labeled<'a, T> {
+    #[dynamic_len]
+    label: &'a str,
+    value: T,
+}
+
+#[test]
+fn test_bound_per_impl() {
+    let owned = Owned { items: vec![7u16] };
+    assert_eq!(roundtrip(&owned), vec![1, 0, 7]);
+
+    let labeled = Labeled { label: "id", value: 3u8 };
+    let config = SerializationConfig::default();
+    let bytes = labeled.to_bytes(&config).unwrap();
+    assert_eq!(Labeled::<u8>::from_bytes(&bytes, &config).unwrap(), labeled);
+}
@@ -0,0 +1,95 @@
+use crate::{DeserializationErrorKind, FromBytes, SerializationConfig, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[magic = b"IMG\r\n"]
+struct ImageHeader {
+    #[constant = 2]
+    version: u16,
+    width: u16,
+    #[constant = b'~']
+    #[bits = 7]
+    end: u8,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+#[magic = b"\x7eM"]
+enum Frame {
+    Ping,
+    Data(u8),
+}
+
+#[test]
+fn test_magic_and_constants() {
+    let config = SerializationConfig::default();
+    // The value of a constant field is ignored on write
+    let value = ImageHeader { version: 0, width: 640, end: 0 };
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![b'I', b'M', b'G', b'\r', b'\n', 0, 2, 0x02, 0x80, b'~']);
+
+    let decoded = ImageHeader::from_bytes(&bytes, &config).unwrap();
+    assert_eq!(decoded, ImageHeader { version: 2, width: 640, end: b'~' });
+}
+
+#[test]
+fn test_invalid_magic() {
+    let config = SerializationConfig::default();
+    let mut bytes = ImageHeader { version: 2, width: 1, end: b'~' }.to_bytes(&config).unwrap();
+    bytes[1] = b'X';
+    let error = ImageHeader::from_bytes(&bytes, &config).unwrap_err();
+    assert_eq!(
+        error.kind,
+        DeserializationErrorKind::InvalidMagic { expected: b"IMG\r\n".to_vec(), found: b"IXG\r\n".to_vec() }
+    );
+    assert_eq!(error.offset, Some((0, 0)));
+
+    bytes[1] = b'M';
+    bytes[6] = 3;
+    assert_eq!(
+        ImageHeader::from_bytes(&bytes, &config).unwrap_err().kind,
+        DeserializationErrorKind::InvalidMagic { expected: vec![0, 2], found: vec![0, 3] }
+    );
+
+    assert_eq!(
+        ImageHeader::from_bytes(b"IMG", &config).unwrap_err().kind,
+        DeserializationErrorKind::NotEnoughBytes(Some(2))
+    );
+}
+
+#[test]
+fn test_enum_magic() {
+    let config = SerializationConfig::default();
+    let bytes = Frame::Data(5).to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0x7e, b'M', 1, 5]);
+    assert_eq!(Frame::from_bytes(&bytes, &config).unwrap(), Frame::Data(5));
+    let error = Frame::from_bytes(&[0x7e, b'N', 0], &config).unwrap_err();
+    assert!(matches!(error.kind, DeserializationErrorKind::InvalidMagic { .. }));
+    assert_eq!(error.offset, Some((0, 0)));
+
+    let error = Frame::from_bytes(&[0x7e, b'M', 7], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::UnknownDiscriminant(7));
+    assert_eq!(error.offset, Some((0, 0)));
+}
+
+/// Constants at the limits of their field types. One past them (like `#[constant = 256]` on a `u8`) fails to compile
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Limits {
+    #[constant = 255]
+    a: u8,
+    #[constant = 127]
+    b: i8,
+    #[constant = 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff]
+    c: u128,
+    #[constant = 1.5]
+    d: f32,
+}
+
+#[test]
+fn test_constants_at_type_limits() {
+    let config = SerializationConfig::default();
+    let bytes = Limits { a: 0, b: 0, c: 0, d: 0.0 }.to_bytes(&config).unwrap();
+    // Signed integers are zigzag encoded
+    assert_eq!(bytes[..2], [255, 254]);
+    assert_eq!(bytes[2..18], [0xff; 16]);
+    assert_eq!(bytes[18..], 1.5f32.to_be_bytes());
+    assert_eq!(Limits::from_bytes(&bytes, &config).unwrap(), Limits { a: 255, b: 127, c: u128::MAX, d: 1.5 });
+}
@@ -1,4 +1,62 @@
+/// Serialize `value` with the default config, check that it reads back unchanged and return the bytes
 #[cfg(test)]
-mod tests {
-    // TODO: add some tests here
-}
\ No newline at end of file
+fn roundtrip<T: crate::ToBytes + crate::FromBytesOwned + PartialEq + std::fmt::Debug>(value: &T) -> Vec<u8> {
+    let config = crate::SerializationConfig::default();
+    let bytes = value.to_bytes(&config).unwrap();
+    assert_eq!(&T::from_bytes(&bytes, &config).unwrap(), value);
+    bytes
+}
+
+#[cfg(test)]
+mod traits;
+
+#[cfg(test)]
+mod config;
+
+#[cfg(test)]
+mod generics;
+
+#[cfg(test)]
+mod tuple_structs;
+
+#[cfg(test)]
+mod enums;
+
+#[cfg(test)]
+mod floats;
+
+#[cfg(test)]
+mod bit_fields;
+
+#[cfg(test)]
+mod magic;
+
+#[cfg(test)]
+mod fuzz;
+
+#[cfg(test)]
+mod errors;
+
+#[cfg(test)]
+mod borrowed;
+
+#[cfg(test)]
+mod byte_blobs;
+
+#[cfg(test)]
+mod streams;
+
+#[cfg(all(test, feature = "tokio-codec"))]
+mod codec;
+
+#[cfg(test)]
+mod partial;
+
+#[cfg(test)]
+mod bit_io;
+
+#[cfg(test)]
+mod buffers;
+
+#[cfg(test)]
+mod allocations;
@@ -0,0 +1,147 @@
+use crate::{Decoded, DeserializationErrorKind, FromBytes, SerializationConfig, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Packet {
+    #[bits = 3]
+    version: u8,
+    #[bits = 13]
+    flags: u16,
+    kind: Kind,
+    #[dynamic]
+    sequence: u64,
+    #[dynamic_len]
+    name: String,
+    #[dynamic_len]
+    readings: Vec<Reading>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Kind {
+    Data,
+    Ack(u32),
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Reading {
+    #[bits = 40]
+    timestamp: u64,
+    value: i16,
+}
+
+#[test]
+fn test_every_prefix_is_incomplete() {
+    let config = SerializationConfig::default();
+    let packet = Packet {
+        version: 5,
+        flags: 0x1abc,
+        kind: Kind::Ack(7),
+        sequence: 300_000,
+        name: "sensor".into(),
+        readings: vec![Reading { timestamp: 1 << 39, value: -3 }, Reading { timestamp: 2, value: 9 }],
+    };
+    let bytes = packet.to_bytes(&config).unwrap();
+
+    for len in 0..bytes.len() {
+        let Ok(Decoded::Incomplete { needed }) = Packet::try_from_bytes(&bytes[..len], &config) else {
+            panic!("Expected incomplete at {} bytes", len);
+        };
+        if let Some(needed) = needed {
+            assert!(len + needed <= bytes.len());
+        }
+    }
+    assert_eq!(Packet::try_from_bytes(&bytes, &config), Ok(Decoded::Complete(packet, bytes.len())));
+}
+
+#[test]
+fn test_incomplete_needed() {
+    let config = SerializationConfig::default();
+    let packet = Packet {
+        version: 5,
+        flags: 0x1abc,
+        kind: Kind::Ack(7),
+        sequence: 300_000,
+        name: "sensor".into(),
+        readings: vec![Reading { timestamp: 1 << 39, value: -3 }, Reading { timestamp: 2, value: 9 }],
+    };
+    let bytes = packet.to_bytes(&config).unwrap();
+
+    // The bit fields take 2 bytes, the discriminant 1 and the u32 of Ack 4
+    assert_eq!(Packet::try_from_bytes(&bytes[..4], &config), Ok(Decoded::Incomplete { needed: Some(3) }));
+    // The size of a dynamic integer is only known at its last byte
+    assert_eq!(Packet::try_from_bytes(&bytes[..8], &config), Ok(Decoded::Incomplete { needed: None }));
+}
+
+#[test]
+fn test_complete_with_trailing_bytes() {
+    let config = SerializationConfig::default();
+    let packet = Packet {
+        version: 5,
+        flags: 0x1abc,
+        kind: Kind::Ack(7),
+        sequence: 300_000,
+        name: "sensor".into(),
+        readings: vec![Reading { timestamp: 1 << 39, value: -3 }, Reading { timestamp: 2, value: 9 }],
+    };
+    let mut bytes = packet.to_bytes(&config).unwrap();
+    let len = bytes.len();
+    bytes.extend_from_slice(&[0xff; 3]);
+
+    let Ok(Decoded::Complete(decoded, used)) = Packet::try_from_bytes(&bytes, &config) else {
+        panic!("Expected complete");
+    };
+    assert_eq!(decoded, packet);
+    assert_eq!(used, len);
+}
+
+#[test]
+fn test_format_error_is_not_incomplete() {
+    let config = SerializationConfig::default();
+    let packet = Packet {
+        version: 5,
+        flags: 0x1abc,
+        kind: Kind::Ack(7),
+        sequence: 300_000,
+        name: "sensor".into(),
+        readings: vec![Reading { timestamp: 1 << 39, value: -3 }, Reading { timestamp: 2, value: 9 }],
+    };
+    let mut bytes = packet.to_bytes(&config).unwrap();
+    bytes[2] = 9;
+
+    let error = Packet::try_from_bytes(&bytes[..3], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::UnknownDiscriminant(9));
+
+    // A length delimited value can't be completed by more input
+    #[derive(ToBytes, FromBytes, Debug, PartialEq)]
+    struct Wrapper {
+        #[dynamic_len]
+        reading: Reading,
+    }
+    let error = Wrapper::try_from_bytes(&[2, 0, 0, 0], &config).unwrap_err();
+    assert_eq!(error.kind, DeserializationErrorKind::LengthTooShort(2));
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Opt {
+    a: u8,
+    b: Option<u16>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Rest {
+    id: u8,
+    rest: String,
+}
+
+#[test]
+fn test_end_of_input_is_not_known() {
+    // More bytes may follow, so the missing option may still come
+    let config = SerializationConfig::default();
+    assert_eq!(Opt::try_from_bytes(&[1], &config), Ok(Decoded::Incomplete { needed: None }));
+    assert_eq!(Opt::try_from_bytes(&[1, 0], &config), Ok(Decoded::Incomplete { needed: Some(1) }));
+    assert_eq!(Opt::try_from_bytes(&[1, 0, 2], &config), Ok(Decoded::Complete(Opt { a: 1, b: Some(2) }, 3)));
+    assert_eq!(Opt::from_bytes(&[1], &config), Ok(Opt { a: 1, b: None }));
+
+    // Neither is the end of the remaining bytes
+    assert_eq!(Rest::try_from_bytes(b"\x07abc", &config), Ok(Decoded::Incomplete { needed: None }));
+    assert_eq!(Rest::from_bytes(b"\x07abc", &config), Ok(Rest { id: 7, rest: "abc".into() }));
+}
@@ -0,0 +1,240 @@
+use std::io::{BufRead, BufReader, Cursor, Read};
+
+use crate::{
+    DeserializationErrorKind, FromBytes, FromBytesOwned, SerializationConfig, StreamError, StreamReader, StreamWriter,
+    ToBytes,
+};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Record {
+    #[bits = 4]
+    kind: u8,
+    #[bits = 12]
+    channel: u16,
+    #[dynamic_len]
+    payload: Vec<u8>,
+    #[dynamic_len]
+    label: String,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Envelope {
+    #[dynamic_len]
+    record: Record,
+}
+
+/// Reader that returns at most one byte per call, like a slow socket
+struct Trickle<R>(R);
+
+impl<R: Read> Read for Trickle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(1);
+        self.0.read(&mut buf[..len])
+    }
+}
+
+#[test]
+fn test_stream_roundtrip() {
+    let config = SerializationConfig::default();
+    let records: Vec<Record> = (0..50)
+        .map(|i| Record { kind: i as u8 % 16, channel: i * 7, payload: vec![i as u8; i as usize * 3], label: format!("r{}", i) })
+        .collect();
+    let mut writer = StreamWriter::new(Vec::new(), config);
+    for record in &records {
+        writer.write(record).unwrap();
+    }
+    let bytes = writer.into_inner().unwrap();
+
+    let mut reader = StreamReader::new(Trickle(Cursor::new(bytes)), config);
+    let mut decoded = Vec::new();
+    while let Some(record) = reader.read::<Record>().unwrap() {
+        decoded.push(record);
+    }
+    assert_eq!(decoded, records);
+}
+
+#[test]
+fn test_read_from_leaves_reader_after_value() {
+    let config = SerializationConfig::default();
+    let records = [
+        Record { kind: 0, channel: 0, payload: vec![], label: "r0".into() },
+        Record { kind: 1, channel: 7, payload: vec![1; 3], label: "r1".into() },
+        Record { kind: 2, channel: 14, payload: vec![2; 6], label: "r2".into() },
+    ];
+    let mut bytes = Vec::new();
+    for record in &records {
+        record.write_to(&mut bytes, &config).unwrap();
+    }
+
+    let mut cursor = Cursor::new(&bytes);
+    let first = Record::read_from(&mut cursor, &config).unwrap();
+    assert_eq!(cursor.position() as usize, first.to_bytes(&config).unwrap().len());
+    assert_eq!(Record::read_from(&mut cursor, &config).unwrap(), records[1]);
+    assert_eq!(Record::read_from(&mut cursor, &config).unwrap(), records[2]);
+    assert_eq!(cursor.position() as usize, bytes.len());
+}
+
+#[test]
+fn test_stream_truncated() {
+    let config = SerializationConfig::default();
+    let record = Record { kind: 5, channel: 35, payload: vec![5; 15], label: "r5".into() };
+    let bytes = record.to_bytes(&config).unwrap();
+
+    let mut reader = StreamReader::new(&bytes[..bytes.len() - 1], config);
+    let Err(StreamError::Io(error)) = reader.read::<Record>() else {
+        panic!("Expected io error");
+    };
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    let mut reader = &bytes[..0];
+    assert!(matches!(Record::read_from(&mut reader, &config), Err(StreamError::Io(_))));
+}
+
+#[test]
+fn test_stream_invalid_data() {
+    let config = SerializationConfig::default();
+    // The envelope says the record has 3 bytes, but its payload needs more. Reading on won't help
+    let mut bytes = vec![3, 0x10, 0x00, 5];
+    let record = Record { kind: 9, channel: 63, payload: vec![9; 27], label: "r9".into() };
+    bytes.extend(record.to_bytes(&config).unwrap());
+
+    let Err(StreamError::Deserialization(error)) = Envelope::read_from(&mut Cursor::new(&bytes), &config) else {
+        panic!("Expected deserialization error");
+    };
+    assert_eq!(error.kind, DeserializationErrorKind::LengthTooShort(3));
+
+    let mut reader = StreamReader::new(Cursor::new(&bytes), config);
+    let error: std::io::Error = reader.read::<Envelope>().unwrap_err().into();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_write_to_serialization_error() {
+    let config = SerializationConfig::default();
+    let mut writer = StreamWriter::new(Vec::new(), config);
+    let record = Record { kind: 16, channel: 0, payload: vec![], label: String::new() };
+    assert!(matches!(writer.write(&record), Err(StreamError::Serialization(_))));
+    // Nothing of the failed value is written
+    assert!(writer.into_inner().unwrap().is_empty());
+}
+
+fn read_all<T: FromBytesOwned, R: Read>(reader: R) -> Result<Vec<T>, StreamError> {
+    let mut reader = StreamReader::new(reader, SerializationConfig::default());
+    let mut values = Vec::new();
+    while let Some(value) = reader.read()? {
+        values.push(value);
+    }
+    Ok(values)
+}
+
+#[test]
+fn test_stream_large_input() {
+    let config = SerializationConfig::default();
+    let mut bytes = Vec::new();
+    let blob = |kind| Record { kind, channel: 2, payload: vec![0xab; 100_000], label: "blob".into() };
+    blob(1).write_to(&mut bytes, &config).unwrap();
+    blob(2).write_to(&mut bytes, &config).unwrap();
+
+    let values: Vec<Record> = read_all(Cursor::new(bytes)).unwrap();
+    assert_eq!(values, vec![blob(1), blob(2)]);
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Samples {
+    #[dynamic_len]
+    values: Vec<u16>,
+}
+
+#[test]
+fn test_read_from_large_record() {
+    let config = SerializationConfig::default();
+    let samples = Samples { values: (0..50_000).collect() };
+    let mut bytes = samples.to_bytes(&config).unwrap();
+    bytes.push(0xee);
+
+    // The value is read through the reader's buffer, the byte after it is left in the reader
+    let mut reader = BufReader::new(Cursor::new(&bytes));
+    assert_eq!(Samples::read_from(&mut reader, &config).unwrap(), samples);
+    assert_eq!(reader.fill_buf().unwrap(), [0xee]);
+}
+
+#[test]
+fn test_malicious_length_prefix() {
+    // A payload of 4 GiB, and an envelope of about 2^63 bytes, in a few bytes of input.
+    // Decoding runs into the end of the input, without allocating for the claimed lengths
+    let config = SerializationConfig::default();
+    let payload = [0x10, 0x00, 0xff, 0xff, 0xff, 0xff, 0x0f];
+    let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+    let results = [
+        Record::read_from(&mut &payload[..], &config).map(drop),
+        StreamReader::new(&payload[..], config).read::<Record>().map(drop),
+        Envelope::read_from(&mut &huge[..], &config).map(drop),
+        StreamReader::new(&huge[..], config).read::<Envelope>().map(drop),
+    ];
+    for result in results {
+        let Err(StreamError::Io(error)) = result else {
+            panic!("Expected io error");
+        };
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    // With a limit the length is rejected before reading on
+    let config = SerializationConfig { max_length: 1024, ..Default::default() };
+    let Err(StreamError::Deserialization(error)) = Envelope::read_from(&mut &huge[..], &config) else {
+        panic!("Expected deserialization error");
+    };
+    assert!(matches!(error.kind, DeserializationErrorKind::LengthLimitExceeded(_, 1024)));
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Rest {
+    id: u8,
+    rest: Vec<u8>,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Flagged {
+    flag: bool,
+    value: Option<u8>,
+}
+
+#[test]
+fn test_stream_not_self_delimiting() {
+    // The end of the stream is not the end of the value, so there is no length to read the rest with
+    let config = SerializationConfig::default();
+    let bytes: Vec<u8> = (0..100).collect();
+    let mut reader = BufReader::with_capacity(10, Cursor::new(&bytes));
+    let Err(StreamError::Deserialization(error)) = Rest::read_from(&mut reader, &config) else {
+        panic!("Expected deserialization error");
+    };
+    assert_eq!(error.kind, DeserializationErrorKind::NotSelfDelimiting);
+    assert_eq!(error.to_string(), "value reads until the end of the input, which a stream doesn't have in Rest.rest at byte 1, bit 0");
+
+    let mut reader = StreamReader::new(Cursor::new(&bytes), config);
+    assert!(matches!(reader.read::<Flagged>(), Err(StreamError::Deserialization(_))));
+
+    // From a slice the end is known
+    assert_eq!(Rest::from_bytes(&bytes, &config).unwrap().rest.len(), 99);
+}
+
+#[test]
+fn test_read_from_unbuffered() {
+    // Every read is a call on the reader, of at most the bytes the value still needs
+    let config = SerializationConfig::default();
+    let records = [
+        Record { kind: 3, channel: 4095, payload: vec![7; 300], label: "first".into() },
+        Record { kind: 15, channel: 1, payload: vec![], label: "second".into() },
+    ];
+    let mut bytes = Vec::new();
+    for record in &records {
+        record.write_to(&mut bytes, &config).unwrap();
+    }
+    bytes.push(0xee);
+
+    let mut reader = Trickle(Cursor::new(&bytes));
+    assert_eq!(Record::read_from(&mut reader, &config).unwrap(), records[0]);
+    assert_eq!(Record::read_from(&mut reader, &config).unwrap(), records[1]);
+    let mut next = Vec::new();
+    reader.read_to_end(&mut next).unwrap();
+    assert_eq!(next, [0xee]);
+}
@@ -0,0 +1,66 @@
+use super::roundtrip;
+use crate::{BitReader, BitWriter, DeserializationError, FromBytes, SerializationConfig, SerializationError, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Header {
+    #[bits = 3]
+    version: u8,
+    flag: bool,
+    length: u16,
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+enum Command {
+    Ping,
+    Set(u8),
+    Move { x: i32 },
+}
+
+/// Hand-written implementation: a timestamp stored as seconds in a u32
+#[derive(Debug, PartialEq)]
+struct Timestamp(u32);
+
+impl ToBytes for Timestamp {
+    fn to_bytes_internal(&self, writer: &mut BitWriter, config: &SerializationConfig) -> Result<(), SerializationError> {
+        writer.write_fixed(self.0, config)
+    }
+}
+
+impl FromBytes<'_> for Timestamp {
+    fn from_bytes_internal(reader: &mut BitReader, config: &SerializationConfig) -> Result<Self, DeserializationError> {
+        Ok(Timestamp(reader.read_fixed(config)?))
+    }
+}
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Event {
+    header: Header,
+    at: Timestamp,
+}
+
+#[test]
+fn test_generic_roundtrip() {
+    let header = Header { version: 5, flag: true, length: 300 };
+    assert_eq!(roundtrip(&header), vec![0b1101, 1, 44]);
+    assert_eq!(roundtrip(&Command::Move { x: -20 }), vec![2, 0, 0, 0, 39]);
+}
+
+#[test]
+fn test_trait_objects() {
+    let items: Vec<Box<dyn ToBytes>> = vec![Box::new(Command::Ping), Box::new(Command::Set(7))];
+    let config = SerializationConfig::default();
+    let bytes: Vec<Vec<u8>> = items.iter().map(|i| i.to_bytes(&config).unwrap()).collect();
+    assert_eq!(bytes, vec![vec![0], vec![1, 7]]);
+}
+
+#[test]
+fn test_hand_written_impl_in_derived_struct() {
+    let event = Event {
+        header: Header { version: 1, flag: false, length: 2 },
+        at: Timestamp(0x01020304),
+    };
+    let config = SerializationConfig::default();
+    let bytes = event.to_bytes(&config).unwrap();
+    assert_eq!(bytes, vec![0b0000_0001, 0, 2, 1, 2, 3, 4]);
+    assert_eq!(Event::from_bytes(&bytes, &config).unwrap(), event);
+}
@@ -0,0 +1,37 @@
+use super::roundtrip;
+use crate::{FromBytes, SerializationConfig, ToBytes};
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Rssi(#[bits = 5] u8);
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Marker;
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Reading(Rssi, bool, Marker, u16);
+
+#[derive(ToBytes, FromBytes, Debug, PartialEq)]
+struct Blob(u8, #[length_determined_by = "0"] Vec<u8>, bool, #[toggled_by = "2"] Option<u8>);
+
+#[test]
+fn test_newtype() {
+    assert_eq!(roundtrip(&Rssi(17)), vec![17]);
+    assert!(Rssi(32).to_bytes(&SerializationConfig::default()).is_err());
+}
+
+#[test]
+fn test_unit_struct() {
+    assert_eq!(roundtrip(&Marker), Vec::<u8>::new());
+}
+
+#[test]
+fn test_nested_tuple_structs() {
+    assert_eq!(roundtrip(&Reading(Rssi(3), true, Marker, 0x0102)), vec![0b0010_0011, 1, 2]);
+}
+
+#[test]
+fn test_positional_references() {
+    assert_eq!(roundtrip(&Blob(2, vec![7, 8], true, Some(9))), vec![2, 7, 8, 1, 9]);
+    assert_eq!(roundtrip(&Blob(0, vec![], false, None)), vec![0, 0]);
+    assert!(Blob(3, vec![1], false, None).to_bytes(&SerializationConfig::default()).is_err());
+}
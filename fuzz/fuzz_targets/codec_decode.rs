@@ -0,0 +1,38 @@
+#![no_main]
+
+use binary_codec::fuzzing::{config, Everything, Kind, Packed};
+use binary_codec::{BinaryCodec, FromBytesOwned, SerializationConfig};
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use tokio_util::codec::Decoder;
+
+/// Feed the bytes to the decoder in chunks, like they arrive from a socket, and decode frames until it waits or fails
+fn check<T: FromBytesOwned>(bytes: &[u8], chunk: usize, length_delimited: bool, config: SerializationConfig) {
+    let codec: BinaryCodec<T> = if length_delimited { BinaryCodec::length_delimited(config) } else { BinaryCodec::new(config) };
+    let mut codec = codec.with_max_frame_length(1024);
+    let mut src = BytesMut::new();
+    for chunk in bytes.chunks(chunk) {
+        src.extend_from_slice(chunk);
+        loop {
+            match codec.decode(&mut src) {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the config, the second the chunk size and whether frames are length delimited
+    let [selector, options, bytes @ ..] = data else {
+        return;
+    };
+    let config = config(*selector);
+    let chunk = 1 + (*options & 0x7f) as usize;
+    let length_delimited = *options & 0x80 != 0;
+
+    check::<Everything>(bytes, chunk, length_delimited, config);
+    check::<Packed>(bytes, chunk, length_delimited, config);
+    check::<Kind>(bytes, chunk, length_delimited, config);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use binary_codec::fuzzing::{check, config, Everything, Kind, Packed};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the config
+    let Some((&selector, bytes)) = data.split_first() else {
+        return;
+    };
+    let config = config(selector);
+
+    check::<Everything>(bytes, &config);
+    check::<Packed>(bytes, &config);
+    check::<Kind>(bytes, &config);
+});
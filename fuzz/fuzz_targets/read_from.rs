@@ -0,0 +1,32 @@
+#![no_main]
+
+use std::io::BufReader;
+
+use binary_codec::fuzzing::{config, Everything, Inner, Kind, Packed};
+use binary_codec::{FromBytesOwned, SerializationConfig, StreamReader};
+use libfuzzer_sys::fuzz_target;
+
+/// Read values until the input ends or is invalid, both from a buffered reader and a `StreamReader`
+fn check<T: FromBytesOwned>(bytes: &[u8], capacity: usize, config: &SerializationConfig) {
+    let mut reader = BufReader::with_capacity(capacity, bytes);
+    while T::read_from(&mut reader, config).is_ok() {}
+
+    let mut reader = StreamReader::new(bytes, *config);
+    while let Ok(Some(_)) = reader.read::<T>() {}
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the config, the second the size of the read buffer
+    let [selector, capacity, bytes @ ..] = data else {
+        return;
+    };
+    let config = config(*selector);
+    let capacity = 1 + *capacity as usize;
+
+    // Everything and Packed end in fields that read until the end of the input, so they stop at
+    // `NotSelfDelimiting`: still worth running up to there
+    check::<Everything>(bytes, capacity, &config);
+    check::<Packed>(bytes, capacity, &config);
+    check::<Inner>(bytes, capacity, &config);
+    check::<Kind>(bytes, capacity, &config);
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+use binary_codec::fuzzing::{config, Everything, Kind, Packed};
+use binary_codec::{Decoded, FromBytesOwned, SerializationConfig, ToBytes};
+use libfuzzer_sys::fuzz_target;
+
+/// A complete value uses at most the input, an incomplete one needs at least one more byte
+fn check<T: FromBytesOwned + ToBytes>(bytes: &[u8], config: &SerializationConfig) {
+    match T::try_from_bytes(bytes, config) {
+        Ok(Decoded::Complete(value, used)) => {
+            assert!(used <= bytes.len());
+            let _ = value.to_bytes(config);
+        }
+        Ok(Decoded::Incomplete { needed }) => assert_ne!(needed, Some(0)),
+        Err(_) => {}
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the config
+    let Some((&selector, bytes)) = data.split_first() else {
+        return;
+    };
+    let config = config(selector);
+
+    check::<Everything>(bytes, &config);
+    check::<Packed>(bytes, &config);
+    check::<Kind>(bytes, &config);
+});